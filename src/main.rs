@@ -1,8 +1,13 @@
-use clap::{Parser, Subcommand};
+use clap::{builder::FalseyValueParser, parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-use comfy_table::presets::UTF8_FULL;
+use comfy_table::presets::{ASCII_FULL, ASCII_MARKDOWN, NOTHING, UTF8_FULL};
 use comfy_table::*;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "porty", version, about = "Local port inspector")]
@@ -11,12 +16,363 @@ struct Cli {
     cmd: Option<Cmd>,
 
     /// Show verbose output including executable paths
-    #[arg(short, long, global = true)]
+    #[arg(short, long, global = true, env = "PORTY_VERBOSE", value_parser = FalseyValueParser::new())]
     verbose: bool,
 
     /// Enable colored output (green for dev, red for unknown, yellow for system)
+    #[arg(short, long, global = true, env = "PORTY_COLORS", value_parser = FalseyValueParser::new())]
+    colors: bool,
+
+    /// Don't print the banner
     #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Script-friendly output: quiet, no colors, no wrapping, compact format
+    /// (flags given after it still apply)
+    #[arg(long, global = true)]
+    machine: bool,
+
+    /// Path to the config file (defaults to ~/.config/porty/config.toml)
+    #[arg(long, global = true, env = "PORTY_CONFIG", value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Table border style
+    #[arg(long, global = true, value_enum, default_value_t = TableStyle::Rounded)]
+    style: TableStyle,
+
+    /// Table width in columns (defaults to the terminal width, or 100 when piped)
+    #[arg(long, global = true, value_name = "N", conflicts_with = "wide")]
+    width: Option<u16>,
+
+    /// Don't wrap the table to the terminal width
+    #[arg(long, global = true)]
+    wide: bool,
+
+    /// Probe each listed port and add a HEALTH column
+    #[arg(long, global = true)]
+    check: bool,
+
+    /// Output format
+    #[arg(long, global = true, env = "PORTY_FORMAT", value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
+    /// Shorthand for --format json (wins over --format and PORTY_FORMAT)
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Indent JSON output and sort its keys, for reading and diffing
+    #[arg(long, global = true)]
+    pretty: bool,
+
+    /// Shorthand for --format json --pretty
+    #[arg(long, global = true, conflicts_with = "json")]
+    json_pretty: bool,
+
+    /// Add a SERVICE column naming each port's usual service (from /etc/services)
+    #[arg(long, global = true)]
+    services: bool,
+
+    /// Add a CONNS column with established connections per port
+    #[arg(long, global = true)]
+    connections: bool,
+
+    /// Add a CWD column with each process's working directory
+    #[arg(long, global = true)]
+    cwd: bool,
+
+    /// Collapse the listing to one row per process, listing all its ports
+    #[arg(long, global = true, value_enum, value_name = "KEY")]
+    dedupe_by: Option<DedupeKey>,
+
+    /// Table columns to show, in order (e.g. port,process,pid,cwd,mem)
+    #[arg(long, global = true, value_enum, value_delimiter = ',', value_name = "LIST")]
+    columns: Option<Vec<Column>>,
+
+    /// Order of listed ports: keys to compare in turn (e.g. kind,port or -mem), each
+    /// ascending except connections (busiest first); a `-` prefix reverses a key.
+    /// Ties fall back to the port
+    #[arg(
+        long,
+        global = true,
+        value_name = "KEYS",
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        default_value = "port",
+        value_parser = parse_sort_spec,
+    )]
+    sort: Vec<SortSpec>,
+
+    /// Print only the number of listed rows, after all filters (processes
+    /// rather than ports with --dedupe-by process)
+    #[arg(long, global = true)]
+    count: bool,
+
+    /// Print only the PIDs of the listed ports, one per line, after all filters
+    #[arg(long, global = true, conflicts_with = "count")]
+    plain_pids: bool,
+
+    /// Exit with status 1 if a listed port is of this category (repeatable)
+    #[arg(long, global = true, value_enum, value_name = "KIND")]
+    fail_on: Vec<Kind>,
+
+    /// Reuse discovery results younger than TTL (default 2s); not used by free/kill
+    #[arg(
+        long,
+        global = true,
+        env = "PORTY_CACHE_TTL",
+        value_name = "TTL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2s",
+        value_parser = parse_duration,
+    )]
+    cache: Option<Duration>,
+
+    /// Only show sockets bound to this address (exact or prefix, e.g. 127.0.0.1 or 192.168.)
+    #[arg(long, global = true, value_name = "ADDR", alias = "only-listening-on")]
+    bind: Option<String>,
+
+    /// Only show processes whose name matches this regular expression
+    #[arg(long, global = true, value_name = "REGEX", value_parser = parse_regex)]
+    process_filter: Option<regex::Regex>,
+
+    /// Match --process-filter against the executable path instead of the name
+    #[arg(long, global = true, requires = "process_filter")]
+    filter_exec_path: bool,
+
+    /// Report failing enrichment helpers (and discovery retries) on stderr
+    #[arg(long, global = true)]
+    debug: bool,
+
+    /// Only map ports to PIDs and names: skip exec paths, command lines and
+    /// Docker/kubectl/Homebrew lookups, for the fastest possible listing
+    #[arg(long, global = true)]
+    no_enrich: bool,
+
+    /// Retry a failed lsof/netstat run this many times before giving up (default from config, 2)
+    #[arg(long, global = true, value_name = "N")]
+    retry: Option<u32>,
+
+    /// Give external commands (lsof, ps, docker, ...) this long in total, e.g. 10 or 10s;
+    /// ones still running then are stopped and their data left out
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    max_time: Option<Duration>,
+
+    /// Include porty's own process and the shell that started it (hidden by default)
+    #[arg(long = "self", global = true)]
+    include_self: bool,
+
+    /// List every user's sockets, not just your own
+    #[arg(long, global = true)]
+    all_users: bool,
+
+    /// Write the output to this file instead of stdout (parent dirs are created)
+    #[arg(short, long, global = true, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Skip Docker lookups (container names in listings and details)
+    #[arg(long, global = true)]
+    no_docker: bool,
+
+    /// Don't pipe long tables through $PAGER
+    #[arg(long, global = true)]
+    no_pager: bool,
+
+    /// Project whose .env, package.json and compose file name expected ports (defaults to the current directory)
+    #[arg(long, global = true, value_name = "PATH")]
+    project_dir: Option<PathBuf>,
+}
+
+/// How listings are rendered
+#[derive(Debug, Clone)]
+struct TableOptions {
+    verbose: bool,
     colors: bool,
+    style: TableStyle,
+    width: Option<u16>,
+    wide: bool,
+    format: OutputFormat,
+    pretty: bool,
+    thresholds: Thresholds,
+    /// Don't shorten long values in the detail card
+    full: bool,
+    /// Columns picked with --columns, replacing the default layout
+    columns: Option<Vec<Column>>,
+    /// --quiet: leave out extras like the resource totals
+    quiet: bool,
+}
+
+impl TableOptions {
+    fn from_cli(cli: &Cli, config: &Config) -> Self {
+        TableOptions {
+            verbose: cli.verbose,
+            colors: cli.colors,
+            style: cli.style,
+            width: cli.width,
+            wide: cli.wide,
+            format: if cli.json || cli.json_pretty { OutputFormat::Json } else { cli.format },
+            pretty: cli.pretty || cli.json_pretty,
+            thresholds: config.thresholds,
+            full: matches!(cli.cmd, Some(Cmd::Port { full: true, .. })),
+            columns: cli.columns.clone(),
+            quiet: cli.quiet,
+        }
+    }
+
+    /// Whether --columns asked for `column`
+    fn wants(&self, column: Column) -> bool {
+        self.columns.as_ref().is_some_and(|columns| columns.contains(&column))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable table (or detail card for `port`)
+    Table,
+    /// JSON on a single line (add --pretty to indent it)
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+    /// Prometheus text exposition format
+    Prometheus,
+    /// One `PORT PID KIND PROCESS` line per port, no borders (`--verbose`
+    /// puts the exec path before PROCESS)
+    Compact,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// A column of the listing table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Column {
+    Port,
+    Process,
+    #[value(alias = "category")]
+    Kind,
+    Pid,
+    /// Usual service for the port (from /etc/services)
+    Service,
+    /// Address families the socket is bound on
+    Bind,
+    #[value(alias = "exec-path")]
+    Exec,
+    /// Working directory of the process
+    Cwd,
+    /// Resident memory of the process
+    Mem,
+    /// Established connections
+    #[value(alias = "connections")]
+    Conns,
+    /// Probe result (probes the port, like --check)
+    Health,
+    Note,
+}
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Port => "PORT",
+            Column::Process => "PROCESS",
+            Column::Kind => "CATEGORY",
+            Column::Pid => "PID",
+            Column::Service => "SERVICE",
+            Column::Bind => "BIND",
+            Column::Exec => "EXEC PATH",
+            Column::Cwd => "CWD",
+            Column::Mem => "MEM",
+            Column::Conns => "CONNS",
+            Column::Health => "HEALTH",
+            Column::Note => "NOTE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DedupeKey {
+    /// One row per PID
+    Process,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortKey {
+    Port,
+    /// Process name, case-insensitively
+    Process,
+    /// Category, in the order dev, database, container, forward, worker, system, unknown
+    #[value(alias = "category")]
+    Kind,
+    Pid,
+    /// Most established connections first (implies --connections)
+    #[value(alias = "conns")]
+    Connections,
+    /// Resident memory
+    Mem,
+}
+
+/// One key of --sort; `descending` is a leading `-`, which reverses the key's order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SortSpec {
+    key: SortKey,
+    descending: bool,
+}
+
+impl SortSpec {
+    fn compare(self, a: &PortEntry, b: &PortEntry) -> std::cmp::Ordering {
+        let name = |e: &PortEntry| e.process.as_deref().map(str::to_lowercase);
+        let order = match self.key {
+            SortKey::Port => a.port.cmp(&b.port),
+            SortKey::Process => name(a).cmp(&name(b)),
+            SortKey::Kind => (a.kind as u8).cmp(&(b.kind as u8)),
+            SortKey::Pid => a.pid.cmp(&b.pid),
+            // Busiest first, as `--sort connections` always has
+            SortKey::Connections => b.connections.cmp(&a.connections),
+            SortKey::Mem => a.memory_kb.cmp(&b.memory_kb),
+        };
+        if self.descending { order.reverse() } else { order }
+    }
+}
+
+/// Order `entries` by each spec in turn, then by port and PID so the output is
+/// the same from run to run
+fn sort_entries(entries: &mut [PortEntry], specs: &[SortSpec]) {
+    entries.sort_by(|a, b| {
+        specs
+            .iter()
+            .map(|spec| spec.compare(a, b))
+            .chain([a.port.cmp(&b.port), a.pid.cmp(&b.pid)])
+            .find(|order| order.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Whether --sort compares by `key`
+fn sorts_by(specs: &[SortSpec], key: SortKey) -> bool {
+    specs.iter().any(|spec| spec.key == key)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum TimeFormat {
+    /// Start time exactly as `ps` reports it
+    #[default]
+    Absolute,
+    /// Elapsed time, e.g. "3h ago"
+    Relative,
+    /// ISO-8601 / RFC 3339 timestamp
+    Iso,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TableStyle {
+    /// UTF-8 borders with rounded corners
+    Rounded,
+    /// ASCII-only borders
+    Ascii,
+    /// No borders, columns separated by spaces
+    Plain,
+    /// Markdown table, ready to paste into docs
+    Markdown,
 }
 
 #[derive(Subcommand)]
@@ -27,309 +383,3857 @@ enum Cmd {
     Dev,
     /// Show dev servers and containers
     Prod,
-    /// Show process info for a specific port
-    Port { port: u16 },
+    /// Show process info for one or more ports
+    Port {
+        /// Port numbers or service names (e.g. 5432 or postgres)
+        #[arg(value_parser = parse_port, required = true)]
+        ports: Vec<u16>,
+        /// Keep refreshing the details until interrupted (Ctrl-C)
+        #[arg(short, long)]
+        watch: bool,
+        /// Seconds between refreshes in watch mode
+        #[arg(short, long, default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Add a random delay of up to this much to each refresh (e.g. 500ms)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        interval_jitter: Option<Duration>,
+        /// In watch mode, append every refresh's listing to this file as a JSON line
+        #[arg(long, value_name = "PATH", requires = "watch")]
+        watch_file: Option<PathBuf>,
+        /// Move the watch file to PATH.1 once it reaches this size (e.g. 512KB, 10MB)
+        #[arg(long, value_name = "SIZE", default_value = "10MB", value_parser = parse_size, requires = "watch_file")]
+        max_size: u64,
+        /// How to show the start time: raw ps output, "3h ago", or ISO-8601
+        #[arg(long, value_enum, default_value_t = TimeFormat::Absolute)]
+        time_format: TimeFormat,
+        /// Sample network throughput (adds about a second)
+        #[arg(short, long)]
+        traffic: bool,
+        /// Show long command lines and environment values in full
+        #[arg(long)]
+        full: bool,
+        /// Look up hostnames for the connected peers (off by default since DNS can be slow)
+        #[arg(long)]
+        resolve_dns: bool,
+    },
+    /// Show the details of two ports side by side, marking what differs
+    Compare {
+        /// First port (number or service name)
+        #[arg(value_parser = parse_port)]
+        first: u16,
+        /// Second port (number or service name)
+        #[arg(value_parser = parse_port)]
+        second: u16,
+    },
+    /// Write a Markdown report of all ports, with details for dev servers and databases
+    Export {
+        /// Report file to write, e.g. ports.md
+        path: PathBuf,
+    },
+    /// Search listeners by process name or executable path
+    Find {
+        /// Text to look for (case-insensitive, letters may be spread out)
+        query: String,
+    },
+    /// Probe a port range on localhost with TCP connects (works without lsof)
+    Scan {
+        /// Port range, e.g. 3000-9000, or a single port
+        #[arg(value_parser = parse_port_range)]
+        range: (u16, u16),
+        /// Only list ports that accepted a connection
+        #[arg(long)]
+        open: bool,
+        /// Connect timeout per port
+        #[arg(long, value_name = "DURATION", default_value = "200ms", value_parser = parse_duration)]
+        timeout: Duration,
+        /// Probe this address instead of loopback (IPv4, then IPv6)
+        #[arg(long, value_name = "IP")]
+        addr: Option<std::net::IpAddr>,
+    },
+    /// Interactive port browser: arrows select, d details, k kill, / filter, q quit
+    Tui {
+        /// Seconds between background rescans
+        #[arg(short, long, default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+    /// Render a saved listing instead of scanning this machine
+    Render {
+        /// Output of `--format json` or `--format ndjson` (`-` reads stdin)
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+    },
     /// Check if a port is available
-    Free { port: u16 },
+    Free {
+        /// Port number or service name (e.g. 6379 or redis)
+        #[arg(value_parser = parse_port)]
+        port: u16,
+        /// Only consider this address, and try binding the port on it (e.g. 127.0.0.1 or 0.0.0.0)
+        #[arg(long, value_name = "IP")]
+        addr: Option<std::net::IpAddr>,
+    },
     /// Kill the process on a specific port
     Kill {
-        port: u16,
+        /// Port number or service name
+        #[arg(required_unless_present_any = ["kind", "name", "pid", "list_signals"], value_parser = parse_port)]
+        port: Option<u16>,
+        /// Target this process directly instead of looking it up by port
+        #[arg(long, conflicts_with_all = ["port", "kind", "name"], value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64))]
+        pid: Option<u32>,
+        /// Target every process of this category (e.g. dev)
+        #[arg(short, long, value_enum)]
+        kind: Option<Kind>,
+        /// Target every process whose name contains this text
+        #[arg(short, long)]
+        name: Option<String>,
         /// Skip confirmation and kill immediately
         #[arg(short, long)]
         force: bool,
+        /// Send only this signal (e.g. HUP, SIGINT or 2) instead of TERM then KILL
+        #[arg(short, long, value_parser = parse_signal)]
+        signal: Option<i32>,
+        /// Wait up to TIMEOUT (default 10s) for the processes to exit; fail if any remain
+        #[arg(
+            long,
+            value_name = "TIMEOUT",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "10s",
+            value_parser = parse_duration,
+        )]
+        wait: Option<Duration>,
+        /// Print the signals this platform supports and exit
+        #[arg(long)]
+        list_signals: bool,
+    },
+    /// Check that the tools porty relies on are installed and working
+    Doctor,
+    /// Attach a note to a port, shown wherever the port appears (without TEXT, print it)
+    Note {
+        /// Port number or service name
+        #[arg(value_parser = parse_port)]
+        port: u16,
+        /// The note, e.g. "my api gateway"
+        text: Option<String>,
+        /// Remove the port's note
+        #[arg(long, conflicts_with = "text")]
+        clear: bool,
     },
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct PortEntry {
     port: u16,
     pid: Option<u32>,
     process: Option<String>,
     exec_path: Option<String>,
     kind: Kind,
+    addresses: Vec<(IpFamily, String)>,
+    /// Listings saved before UDP support are all TCP
+    #[serde(default)]
+    protocol: Protocol,
+    /// Owner of the socket; unknown on Windows
+    #[serde(default)]
+    uid: Option<u32>,
+    /// The port is one the current project expects to use
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    project: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health: Option<Health>,
+    /// Established connections, with --connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    connections: Option<u32>,
+    /// Registered service name for the port, with --services
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    service: Option<String>,
+    /// The user's `porty note` for the port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    /// Homebrew formula whose `brew services` entry runs the process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    brew_service: Option<String>,
+    /// Working directory of the process, with --cwd
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
+    /// Resident memory in KB, with `--columns mem`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    memory_kb: Option<u64>,
+    /// CPU usage of the process in percent, fetched along with `memory_kb`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cpu_percent: Option<f64>,
+    /// The process's other ports, folded into this row by `--dedupe-by process`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    other_ports: Vec<u16>,
 }
 
-#[derive(Debug, Clone)]
+/// Result of a liveness probe against a listener
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Health {
+    Ok,
+    Refused,
+    Timeout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum IpFamily {
+    #[serde(rename = "ipv4")]
+    V4,
+    #[serde(rename = "ipv6")]
+    V6,
+}
+
+#[derive(Debug, Clone, Serialize)]
 struct DetailedPortInfo {
     port: u16,
     pid: u32,
     process_name: String,
     command: String,
+    /// `command` split shell-style into the binary and its arguments
+    exec: String,
+    args: Vec<String>,
     working_dir: Option<String>,
     exec_path: Option<String>,
     user_name: String,
     uid: u32,
-    parent_chain: Vec<(u32, String)>,
-    children: Vec<(u32, String)>,
+    /// Scheduler state, from `ps -o state`
+    state: Option<ProcessState>,
+    /// Ancestors from the oldest down to the direct parent, stopping before init/launchd
+    parent_chain: Vec<ProcessRef>,
+    /// Direct parent, also set when it's init/launchd and so left out of the chain
+    parent_pid: Option<u32>,
+    /// Leader of the process's session, usually the shell or terminal that started it
+    session_leader: Option<ProcessRef>,
+    children: Vec<ProcessRef>,
     uptime: String,
     start_time: String,
+    started_at: Option<DateTime<Local>>,
+    /// The process start: macOS doesn't record when a socket was bound
+    listening_since: String,
+    /// The user's `porty note` for the port
+    note: Option<String>,
     memory_rss: u64,      // in KB
     memory_virtual: u64,  // in KB
     cpu_usage: f64,
     thread_count: u32,
     file_descriptors: u32,
-    listen_addresses: Vec<String>,
+    fd_breakdown: FdBreakdown,
+    listen_addresses: Vec<(IpFamily, String)>,
+    /// LAN address other hosts can use, for wildcard binds
+    reachable_at: Option<String>,
     active_connections: u32,
+    /// Remote ends of the established connections
+    peers: Vec<Peer>,
     other_ports: Vec<u16>,
     env_vars: Vec<(String, String)>,
     kind: Kind,
     docker_info: Option<DockerInfo>,
+    listen_queue: Option<ListenQueue>,
+    traffic: Option<Traffic>,
+    /// Command line that set up the forward, for `Kind::Forward` listeners
+    forwarded_by: Option<String>,
+    kube_forward: Option<KubeForward>,
+    /// Homebrew formula whose `brew services` entry runs the process
+    brew_service: Option<String>,
+    /// Version manager (asdf, mise, rbenv, nvm) that picked the runtime
+    version_manager: Option<VersionManager>,
 }
 
-#[derive(Debug, Clone)]
+/// What a process is doing, as reported by `ps -o state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))] // only built by the macOS detailed view
+enum ProcessState {
+    Running,
+    Sleeping,
+    /// Sleeping for more than about 20 seconds
+    Idle,
+    Stopped,
+    /// Blocked in the kernel, usually on I/O; signals wait until it returns
+    Uninterruptible,
+    /// Exited, but not yet reaped by its parent
+    Zombie,
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+impl ProcessState {
+    /// The first letter of a `ps` state, e.g. "Ss" or "R+"; the rest are flags
+    fn from_ps(state: &str) -> Option<Self> {
+        match state.chars().next()? {
+            'R' => Some(ProcessState::Running),
+            'S' => Some(ProcessState::Sleeping),
+            'I' => Some(ProcessState::Idle),
+            'T' => Some(ProcessState::Stopped),
+            'U' | 'D' => Some(ProcessState::Uninterruptible),
+            'Z' => Some(ProcessState::Zombie),
+            _ => None,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            ProcessState::Running => "running",
+            ProcessState::Sleeping => "sleeping",
+            ProcessState::Idle => "idle",
+            ProcessState::Stopped => "stopped (suspended, e.g. with Ctrl-Z; resume it with kill -CONT)",
+            ProcessState::Uninterruptible => "uninterruptible (waiting in the kernel, usually on I/O; it can't be killed until that returns)",
+            ProcessState::Zombie => "zombie (already exited; its parent hasn't reaped it, so killing it has no effect)",
+        }
+    }
+
+    /// States that explain a listener not answering or not dying
+    fn is_abnormal(self) -> bool {
+        matches!(self, ProcessState::Stopped | ProcessState::Uninterruptible | ProcessState::Zombie)
+    }
+}
+
+/// A related process in the detailed view's process tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ProcessRef {
+    pid: u32,
+    name: String,
+}
+
+/// Remote end of a connection to the port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Peer {
+    address: String,
+    /// Reverse DNS name, with --resolve-dns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+}
+
+/// Target of a `kubectl port-forward`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct KubeForward {
+    target: String, // e.g. "svc/postgres" or a bare pod name
+    namespace: Option<String>,
+}
+
+impl KubeForward {
+    fn describe(&self) -> String {
+        match self.namespace {
+            Some(ref ns) => format!("{} -n {}", self.target, ns),
+            None => self.target.clone(),
+        }
+    }
+}
+
+/// Runtime installed by a version manager, e.g. node 20.11.0 via mise
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))] // only built by the macOS detailed view
+struct VersionManager {
+    manager: String,
+    runtime: String,
+    /// Unknown when the process was only seen under a shim or the manager
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+}
+
+impl VersionManager {
+    fn describe(&self) -> String {
+        match self.version {
+            Some(ref version) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+                format!("{} v{} via {}", self.runtime, version, self.manager)
+            }
+            Some(ref version) => format!("{} {} via {}", self.runtime, version, self.manager),
+            None => format!("{} via {}", self.runtime, self.manager),
+        }
+    }
+}
+
+/// Open file descriptors by type, from the lsof `t` field
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+struct FdBreakdown {
+    sockets: u32,
+    files: u32,
+    pipes: u32,
+    other: u32,
+}
+
+/// Throughput of the whole process, sampled over a short window
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Traffic {
+    bytes_in_per_sec: f64,
+    bytes_out_per_sec: f64,
+}
+
+/// Extra (slower) data to collect for the detailed view
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))] // only the macOS detailed view reads these
+struct DetailOptions {
+    traffic: bool,
+    debug: bool,
+    time_format: TimeFormat,
+    resolve_dns: bool,
+}
+
+/// Accept queue sizes for a listening socket, as reported by `netstat -L`
+#[derive(Debug, Clone, Copy, Serialize)]
+struct ListenQueue {
+    queued: u32,      // connections waiting to be accepted
+    incomplete: u32,  // handshakes still in progress
+    max: u32,         // backlog limit
+    /// Connections dropped because some listen queue was full, system-wide
+    /// since boot (`netstat -s`); not broken down per socket
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overflows: Option<u64>,
+}
+
+impl ListenQueue {
+    /// At 80% of the backlog a burst of connections starts getting dropped
+    fn nearly_full(&self) -> bool {
+        self.max > 0 && self.queued * 5 >= self.max * 4
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 struct DockerInfo {
     container_id: String,
     container_name: String,
     image: String,
     status: String,
     volumes: Vec<String>,
+    networks: Vec<String>,
+    ip_address: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
 enum Kind {
     Dev,
     Database,
     Container,
+    /// SSH or tunnel port forward on loopback
+    Forward,
+    /// Background job runner (Celery, Sidekiq, ...)
+    Worker,
     System,
     Unknown,
 }
 
-fn filter_default(entries: &[PortEntry]) -> Vec<PortEntry> {
-    entries.iter()
-        .filter(|e| matches!(e.kind, Kind::Dev | Kind::Unknown))
-        .cloned()
-        .collect()
-}
+/// User configuration, read from `~/.config/porty/config.toml` by default
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /// Print the ASCII banner on listing commands
+    banner: bool,
+    /// Listing to show when porty runs without a subcommand
+    default_command: Option<DefaultCommand>,
+    /// Extra classification rules, checked before the built-in ones
+    rules: Vec<Rule>,
+    /// Classification by executable location, checked after `rules`
+    path_rules: Vec<PathRule>,
+    /// Changes to the ports that count as dev servers when nothing else matches
+    dev_ports: DevPorts,
+    /// How hard to try when the socket listing command fails
+    discovery: Discovery,
+    /// Resource usage the detailed view highlights in red
+    thresholds: Thresholds,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            banner: true,
+            default_command: None,
+            rules: Vec::new(),
+            path_rules: Vec::new(),
+            dev_ports: DevPorts::default(),
+            discovery: Discovery::default(),
+            thresholds: Thresholds::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Thresholds {
+    cpu_percent: f64,
+    memory_mb: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            cpu_percent: 80.0,
+            memory_mb: 1024,
+        }
+    }
+}
+
+/// Ports classified as Dev by the port fallback, on top of (`add`) or instead of
+/// (`remove`) the built-in ones
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DevPorts {
+    add: Vec<u16>,
+    remove: Vec<u16>,
+}
+
+/// Retries for lsof/netstat, which can fail transiently while racing the
+/// process table on a busy machine
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Discovery {
+    retries: u32,
+    /// Wait before the first retry, doubled for each one after it
+    retry_backoff_ms: u64,
+    /// Set from --debug, not the config file
+    #[serde(skip)]
+    debug: bool,
+    /// Set from --no-enrich, not the config file
+    #[serde(skip)]
+    no_enrich: bool,
+}
+
+impl Default for Discovery {
+    fn default() -> Self {
+        Discovery {
+            retries: 2,
+            retry_backoff_ms: 100,
+            debug: false,
+            no_enrich: false,
+        }
+    }
+}
+
+/// Run a socket listing command, retrying failures as `discovery` allows
+#[cfg(any(target_os = "macos", windows))]
+fn run_discovery_command(program: &str, args: &[&str], discovery: Discovery) -> Result<std::process::Output> {
+    let mut backoff = Duration::from_millis(discovery.retry_backoff_ms);
+    let mut attempt = 0;
+    loop {
+        let output = match std::process::Command::new(program).args(args).output_within() {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => return Err(e.into()),
+            Err(e) => return Err(e).with_context(|| format!("failed to run {program} (is it installed?)")),
+        };
+        if output.status.success() {
+            return Ok(output);
+        }
+        if attempt == discovery.retries {
+            return Err(anyhow::anyhow!("{program} exited with status {}", output.status));
+        }
+        attempt += 1;
+        if discovery.debug {
+            eprintln!(
+                "debug: {program} exited with status {}, retry {attempt}/{} in {}ms",
+                output.status,
+                discovery.retries,
+                backoff.as_millis()
+            );
+        }
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+}
+
+/// The global --max-time, and when the current run of external commands must end
+static TIMEOUT: std::sync::OnceLock<Duration> = std::sync::OnceLock::new();
+static DEADLINE: std::sync::Mutex<Option<std::time::Instant>> = std::sync::Mutex::new(None);
+/// Set once a command was stopped, so partial results aren't cached
+static TIMED_OUT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Give external commands another --max-time from now (once per run, and per
+/// refresh in watch modes)
+fn restart_deadline() {
+    if let Some(timeout) = TIMEOUT.get() {
+        TIMED_OUT.store(false, std::sync::atomic::Ordering::Relaxed);
+        *DEADLINE.lock().unwrap_or_else(|e| e.into_inner()) = Some(std::time::Instant::now() + *timeout);
+    }
+}
+
+fn timed_out() -> bool {
+    TIMED_OUT.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// `Command::output`, but stopped (killed) at the --max-time deadline
+trait OutputWithin {
+    fn output_within(&mut self) -> std::io::Result<std::process::Output>;
+}
+
+impl OutputWithin for std::process::Command {
+    fn output_within(&mut self) -> std::io::Result<std::process::Output> {
+        use std::io::Read;
+        use std::process::Stdio;
+
+        let deadline = *DEADLINE.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(deadline) = deadline else {
+            return self.output();
+        };
+        let step = self.get_program().to_string_lossy().into_owned();
+        let stop = |step: &str| {
+            report_timeout(step);
+            std::io::Error::new(std::io::ErrorKind::TimedOut, format!("{step} timed out (--max-time)"))
+        };
+        if std::time::Instant::now() >= deadline {
+            return Err(stop(&step));
+        }
+
+        let mut child = self.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        // Drain both pipes while waiting, or a chatty child blocks on a full one
+        let drain = |pipe: Option<Box<dyn Read + Send>>| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                if let Some(mut pipe) = pipe {
+                    let _ = pipe.read_to_end(&mut buf);
+                }
+                buf
+            })
+        };
+        let stdout = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+        let stderr = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(std::process::Output {
+                    status,
+                    stdout: stdout.join().unwrap_or_default(),
+                    stderr: stderr.join().unwrap_or_default(),
+                });
+            }
+            if std::time::Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(stop(&step));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// Name each program stopped by --max-time once on stderr
+fn report_timeout(step: &str) {
+    static REPORTED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+    TIMED_OUT.store(true, std::sync::atomic::Ordering::Relaxed);
+    let mut reported = REPORTED.lock().unwrap_or_else(|e| e.into_inner());
+    if !reported.iter().any(|s| s == step) {
+        let timeout = TIMEOUT.get().copied().unwrap_or_default();
+        eprintln!("porty: {step} timed out (--max-time {}s); its data is missing", format_float(timeout.as_secs_f64(), 1));
+        reported.push(step.to_string());
+    }
+}
+
+/// Listing commands that can stand in for bare `porty`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DefaultCommand {
+    All,
+    Dev,
+    Prod,
+}
+
+/// Classify any process whose name contains `process` as `kind`
+#[derive(Debug, Clone, Deserialize)]
+struct Rule {
+    process: String,
+    kind: Kind,
+}
+
+/// Classify any process whose executable lives under the `prefix` directory as `kind`
+#[derive(Debug, Clone, Deserialize)]
+struct PathRule {
+    prefix: String,
+    kind: Kind,
+}
+
+impl PathRule {
+    fn matches(&self, exec_path: &str) -> bool {
+        let prefix = self.prefix.trim_end_matches(['/', '\\']);
+        // Match whole directories: /opt/tools covers /opt/tools/bin/x, not /opt/toolsmith
+        exec_path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '\\']))
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("porty").join("config.toml"))
+}
+
+/// The --config given on the command line, which notes are kept beside
+static CONFIG_PATH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// Where `porty note` keeps its notes, next to the config file
+fn notes_path() -> Option<PathBuf> {
+    let config = CONFIG_PATH.get().cloned().or_else(default_config_path)?;
+    Some(config.with_file_name("notes.toml"))
+}
+
+/// Notes by port; empty when there is no notes file yet
+fn read_notes() -> Result<std::collections::BTreeMap<u16, String>> {
+    let Some(path) = notes_path().filter(|p| p.exists()) else {
+        return Ok(std::collections::BTreeMap::new());
+    };
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    // Lines look like: 8087 = "my api gateway"
+    let raw: std::collections::BTreeMap<String, String> = toml::from_str(&text)
+        .with_context(|| format!("invalid notes file {}", path.display()))?;
+    raw.into_iter()
+        .map(|(port, note)| {
+            let port = port.parse().with_context(|| format!("invalid port '{port}' in {}", path.display()))?;
+            Ok((port, note))
+        })
+        .collect()
+}
+
+fn write_notes(notes: &std::collections::BTreeMap<u16, String>) -> Result<()> {
+    let path = notes_path().context("can't find a home directory for the notes file")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let raw: std::collections::BTreeMap<String, &String> =
+        notes.iter().map(|(port, note)| (port.to_string(), note)).collect();
+    let text = toml::to_string(&raw).context("failed to serialize notes")?;
+    std::fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn cmd_note(port: u16, text: Option<&str>, clear: bool) -> Result<()> {
+    let mut notes = read_notes()?;
+    match text {
+        Some(text) => {
+            notes.insert(port, text.to_string());
+            write_notes(&notes)?;
+        }
+        None if clear => {
+            if notes.remove(&port).is_some() {
+                write_notes(&notes)?;
+            }
+        }
+        None => match notes.get(&port) {
+            Some(note) => println!("{note}"),
+            None => println!("No note for port {port}"),
+        },
+    }
+    Ok(())
+}
+
+/// Attach `porty note`s to the entries; a broken notes file is reported, not fatal
+fn apply_notes(entries: &mut [PortEntry]) {
+    let notes = match read_notes() {
+        Ok(notes) => notes,
+        Err(e) => {
+            eprintln!("warning: {e:#}");
+            return;
+        }
+    };
+    for entry in entries.iter_mut() {
+        entry.note = notes.get(&entry.port).cloned();
+    }
+}
+
+/// Load the config from an explicit path (`--config` / `PORTY_CONFIG`) or the default location.
+///
+/// The default file is optional, but an explicitly requested one must exist.
+fn load_config(explicit: Option<&Path>) -> Result<Config> {
+    let path = match explicit {
+        Some(path) => {
+            if !path.exists() {
+                return Err(anyhow::anyhow!("config file {} does not exist", path.display()));
+            }
+            path.to_path_buf()
+        }
+        None => match default_config_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        },
+    };
+
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read config file {}", path.display()))?;
+    toml::from_str(&text)
+        .with_context(|| format!("invalid config file {}", path.display()))
+}
+
+/// Ports a project expects to use, from its `.env`, `package.json` scripts and
+/// compose file. Missing or unreadable files are skipped.
+fn project_ports(dir: &Path) -> std::collections::BTreeSet<u16> {
+    let mut ports = std::collections::BTreeSet::new();
+    if let Ok(text) = std::fs::read_to_string(dir.join(".env")) {
+        ports.extend(env_file_ports(&text));
+    }
+    if let Ok(text) = std::fs::read_to_string(dir.join("package.json")) {
+        ports.extend(package_json_ports(&text));
+    }
+    for name in ["docker-compose.yml", "docker-compose.yaml", "compose.yml", "compose.yaml"] {
+        if let Ok(text) = std::fs::read_to_string(dir.join(name)) {
+            ports.extend(compose_ports(&text));
+        }
+    }
+    ports
+}
+
+/// `PORT=3000`, `DB_PORT="5432"`, `export VITE_PORT=5173`
+fn env_file_ports(text: &str) -> Vec<u16> {
+    text.lines()
+        .map(|line| line.trim())
+        .map(|line| line.strip_prefix("export ").unwrap_or(line))
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| key.trim().to_uppercase().ends_with("PORT"))
+        .filter_map(|(_, value)| value.trim().trim_matches(['"', '\'']).parse().ok())
+        .collect()
+}
+
+/// `--port 3000`, `--port=3000`, `-p 3000` or `PORT=3000` in npm scripts
+fn package_json_ports(text: &str) -> Vec<u16> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Vec::new();
+    };
+    let Some(scripts) = json.get("scripts").and_then(|s| s.as_object()) else {
+        return Vec::new();
+    };
+
+    let mut ports = Vec::new();
+    for script in scripts.values().filter_map(|v| v.as_str()) {
+        let words: Vec<&str> = script.split_whitespace().collect();
+        for (i, word) in words.iter().enumerate() {
+            let value = match *word {
+                "--port" | "-p" => words.get(i + 1).copied(),
+                _ => word.strip_prefix("--port=").or_else(|| word.strip_prefix("PORT=")),
+            };
+            if let Some(port) = value.and_then(|v| v.parse().ok()) {
+                ports.push(port);
+            }
+        }
+    }
+    ports
+}
+
+/// Host ports published by a compose file: `ports:` items such as
+/// `"3000:3000"` or `127.0.0.1:8080:80`, and long-form `published: 8080`
+fn compose_ports(text: &str) -> Vec<u16> {
+    let mut ports = Vec::new();
+    let mut ports_indent: Option<usize> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if let Some(value) = trimmed.strip_prefix("published:") {
+            if let Ok(port) = value.trim().trim_matches(['"', '\'']).parse() {
+                ports.push(port);
+            }
+            continue;
+        }
+        if trimmed.starts_with("ports:") {
+            ports_indent = Some(indent);
+            continue;
+        }
+
+        let Some(block_indent) = ports_indent else {
+            continue;
+        };
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| indent >= block_indent) {
+            let item = item.trim().trim_matches(['"', '\'']);
+            let item = item.split('/').next().unwrap_or(item);
+            // HOST:CONTAINER or IP:HOST:CONTAINER; a bare container port isn't published
+            let mut parts = item.rsplit(':').skip(1);
+            if let Some(host) = parts.next() {
+                let range = match host.split_once('-') {
+                    Some((start, end)) => start.parse::<u16>().ok().zip(end.parse::<u16>().ok()),
+                    None => host.parse::<u16>().ok().map(|p| (p, p)),
+                };
+                if let Some((start, end)) = range {
+                    ports.extend(start..=end);
+                }
+            }
+        } else if !trimmed.is_empty() && indent <= block_indent {
+            ports_indent = None;
+        }
+    }
+    ports
+}
+
+fn filter_default(entries: &[PortEntry]) -> Vec<PortEntry> {
+    entries.iter()
+        .filter(|e| matches!(e.kind, Kind::Dev | Kind::Unknown))
+        .cloned()
+        .collect()
+}
+
+fn filter_dev(entries: &[PortEntry]) -> Vec<PortEntry> {
+    entries.iter()
+        .filter(|e| matches!(e.kind, Kind::Dev))
+        .cloned()
+        .collect()
+}
+
+fn filter_find(entries: &[PortEntry], query: &str) -> Vec<PortEntry> {
+    entries.iter()
+        .filter(|e| {
+            e.process.as_deref().is_some_and(|p| fuzzy_match(p, query))
+                || e.exec_path.as_deref().is_some_and(|p| fuzzy_match(p, query))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Case-insensitive match where the query letters must appear in order,
+/// but not necessarily next to each other ("pg" matches "postgres")
+fn fuzzy_match(haystack: &str, query: &str) -> bool {
+    let haystack = haystack.to_lowercase();
+    let query = query.to_lowercase();
+    if haystack.contains(&query) {
+        return true;
+    }
+
+    let mut haystack_chars = haystack.chars();
+    query.chars().all(|q| haystack_chars.any(|h| h == q))
+}
+
+fn parse_regex(value: &str) -> Result<regex::Regex, String> {
+    regex::Regex::new(value).map_err(|e| e.to_string())
+}
+
+/// Keep entries whose process name (or exec path) matches `pattern`
+fn filter_process(entries: Vec<PortEntry>, pattern: &regex::Regex, exec_path: bool) -> Vec<PortEntry> {
+    entries.into_iter()
+        .filter(|e| {
+            let subject = if exec_path { e.exec_path.as_deref() } else { e.process.as_deref() };
+            subject.is_some_and(|s| pattern.is_match(s))
+        })
+        .collect()
+}
+
+fn filter_bind(entries: Vec<PortEntry>, bind: &str) -> Vec<PortEntry> {
+    let bind = bind.trim_start_matches('[').trim_end_matches(']');
+    // Wildcard binds may be spelled "*", "0.0.0.0" or "::" depending on the source
+    let wildcard = matches!(bind, "*" | "0.0.0.0" | "::");
+
+    entries.into_iter()
+        .filter(|e| {
+            e.addresses.iter().any(|(_, addr)| {
+                let host = address_host(addr);
+                host.starts_with(bind) || (wildcard && matches!(host, "*" | "0.0.0.0" | "::"))
+            })
+        })
+        .collect()
+}
+
+/// Drop porty's own process and its parent (usually the invoking shell). Neither
+/// normally listens, but helpers they spawn can briefly show up.
+fn filter_self(entries: Vec<PortEntry>) -> Vec<PortEntry> {
+    let own = std::process::id();
+    #[cfg(unix)]
+    let parent = Some(std::os::unix::process::parent_id());
+    #[cfg(not(unix))]
+    let parent: Option<u32> = None;
+
+    entries.into_iter()
+        .filter(|e| e.pid.is_none_or(|pid| pid != own && Some(pid) != parent))
+        .collect()
+}
+
+/// Keep the current user's sockets (everything when running as root)
+fn filter_own_user(entries: Vec<PortEntry>) -> Vec<PortEntry> {
+    match current_uid() {
+        Some(uid) => filter_own(entries, uid),
+        None => entries,
+    }
+}
+
+/// Keep sockets owned by `uid`; entries whose owner is unknown are kept
+fn filter_own(entries: Vec<PortEntry>, uid: u32) -> Vec<PortEntry> {
+    entries
+        .into_iter()
+        .filter(|e| e.uid.is_none_or(|owner| owner == uid))
+        .collect()
+}
+
+/// The user whose sockets are listed by default. `None` (show everything)
+/// for root, who can see and kill every process anyway, and on Windows.
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    let uid = unsafe { libc::geteuid() };
+    (uid != 0).then_some(uid)
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> Option<u32> {
+    None
+}
+
+fn filter_prod(entries: &[PortEntry]) -> Vec<PortEntry> {
+    entries.iter()
+        .filter(|e| matches!(e.kind, Kind::Dev | Kind::Container))
+        .cloned()
+        .collect()
+}
+
+/// Whether an ssh command line asks the client to listen: a local (-L) or
+/// dynamic (-D) forward. Flags can be combined, as in `-fNL 8080:db:5432`.
+fn ssh_forwards_locally(command: &str) -> bool {
+    // Options that take a value; whatever follows one in the same word is that value
+    const WITH_VALUE: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+    command
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|arg| arg.strip_prefix('-'))
+        .any(|flags| flags.chars().find(|c| WITH_VALUE.contains(*c)).is_some_and(|c| c == 'L' || c == 'D'))
+}
+
+fn classify(
+    port: u16,
+    process: Option<&str>,
+    exec_path: Option<&str>,
+    command: Option<&str>,
+    addr: &str,
+    config: &Config,
+) -> Kind {
+    // User rules from the config file win over the built-in ones: names
+    // first, then executable locations
+    if let Some(p) = process {
+        let p = p.to_lowercase();
+        for rule in &config.rules {
+            if p.contains(&rule.process.to_lowercase()) {
+                return rule.kind;
+            }
+        }
+    }
+    if let Some(path) = exec_path
+        && let Some(rule) = config.path_rules.iter().find(|rule| rule.matches(path))
+    {
+        return rule.kind;
+    }
+
+    // Process-based rules take priority (more accurate)
+    if let Some(p) = process {
+        let p = p.to_lowercase();
+
+        // ssh -L / kubectl port-forward / VS Code tunnels listen on loopback
+        // on behalf of a remote server. sshd's loopback listeners are the far
+        // end of someone else's -R, which this machine's user didn't set up.
+        let ssh_forward = p == "ssh" && command.is_some_and(ssh_forwards_locally);
+        if (ssh_forward || matches!(p.as_str(), "kubectl" | "code-tunnel")) && is_loopback(addr) {
+            return Kind::Forward;
+        }
+
+        // macOS system processes (check first to avoid misclassification)
+        if p.contains("launchd") || p.contains("mdnsresponder") || p.contains("cups")
+            || p.contains("controlcenter") || p.contains("airplay") {
+            return Kind::System;
+        }
+
+        // Language runtimes are classified by what they run, below
+        if !is_runtime(&p) {
+            // Dev servers
+            // Next.js names its server process next-server
+            if p.contains("vite") || p == "next" || p.starts_with("next-")
+                || p.contains("rails") || p.contains("django") || p.contains("flask")
+                || p.contains("phoenix") || p.contains("webpack") || p.contains("npm")
+                || p.contains("yarn") || p.contains("puma") || p.contains("unicorn") {
+                return Kind::Dev;
+            }
+
+            // Databases
+            if p.contains("postgres") || p.contains("mysql") || p.contains("redis")
+                || p.contains("mongod") || p.contains("mariadb") || p.contains("couchdb") {
+                return Kind::Database;
+            }
+
+            // Containers
+            if p.contains("docker") || p.contains("containerd") || p.contains("colima")
+                || p.contains("podman") {
+                return Kind::Container;
+            }
+        }
+    }
+
+    // Path and command rules, for runtimes and wrapper scripts
+    if let Some(kind) = classify_by_path(process, exec_path, command) {
+        return kind;
+    }
+
+    // Port-based rules (fallback when process is unknown or doesn't match);
+    // the config's dev ports go first so they can claim a built-in port
+    if config.dev_ports.add.contains(&port) {
+        return Kind::Dev;
+    }
+    match port {
+        3000 | 5173 | 8080 | 8000 | 4200 | 3001 | 5000 | 9000
+            if !config.dev_ports.remove.contains(&port) => Kind::Dev,
+        5432 | 3306 | 6379 | 27017 | 1433 | 5984 => Kind::Database,
+        2375 | 2376 => Kind::Container,
+        631 => Kind::System,
+        _ => Kind::Unknown,
+    }
+}
+
+/// Interpreters whose process name says nothing about the app they serve
+fn is_runtime(name: &str) -> bool {
+    name.contains("node") || name.contains("python") || name.contains("ruby")
+}
+
+/// Binaries shipped with the OS rather than installed by the user
+fn is_system_path(path: &str) -> bool {
+    ["/usr/bin/", "/usr/sbin/", "/usr/libexec/", "/bin/", "/sbin/", "/system/", "c:\\windows\\"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// Classify by what a process is actually running: the script or framework in
+/// its command line and where its binary lives, e.g. `python manage.py
+/// runserver` is a dev server while `/usr/bin/python3 some_daemon.py` isn't.
+fn classify_by_path(process: Option<&str>, exec_path: Option<&str>, command: Option<&str>) -> Option<Kind> {
+    let path = exec_path.unwrap_or("").to_lowercase();
+    let command = command.unwrap_or("").to_lowercase();
+
+    if ["postgres", "mysqld", "redis-server", "mongod", "mariadbd"]
+        .iter()
+        .any(|db| command.contains(db))
+    {
+        return Some(Kind::Database);
+    }
+
+    // Runtimes serve many things; the module or script says which. Checked
+    // before the dev markers, since e.g. `celery -A app` often lives in a venv.
+    let worker_markers = ["celery", "rq worker", "rqworker", "sidekiq", "resque", "dramatiq", "huey_consumer"];
+    if worker_markers.iter().any(|marker| command.contains(marker)) {
+        return Some(Kind::Worker);
+    }
+
+    // Electron apps run on a node bundled inside the .app, but they are desktop
+    // apps, not dev servers
+    let bundled = path.contains(".app/contents/");
+    if bundled && (path.contains("electron") || command.contains("electron") || process.is_some_and(|p| is_runtime(&p.to_lowercase()))) {
+        return Some(Kind::Unknown);
+    }
+
+    let dev_markers = [
+        "manage.py", "runserver", "django", "flask", "uvicorn", "gunicorn", "http.server",
+        "vite", "webpack", "nodemon", "ts-node", "rails", "puma", "unicorn", "phoenix",
+        "jupyter",
+    ];
+    if dev_markers.iter().any(|marker| command.contains(marker)) {
+        return Some(Kind::Dev);
+    }
+    // "next" is too common a word to look for anywhere in the command line, so
+    // only the binary itself counts: `next dev` or `node .../.bin/next start`
+    let mut argv = command.split_whitespace().take(2);
+    if argv.any(|arg| arg.rsplit(['/', '\\']).next() == Some("next")) {
+        return Some(Kind::Dev);
+    }
+
+    // Per-project and per-user toolchains
+    let dev_locations = ["/node_modules/", "/venv/", "/.venv/", "/.nvm/", "/.pyenv/", "/.rbenv/", "/.asdf/"];
+    if dev_locations.iter().any(|dir| path.contains(dir) || command.contains(dir)) {
+        return Some(Kind::Dev);
+    }
+
+    // Container engines, when the name alone was truncated or generic
+    if ["docker", "containerd", "colima", "podman"].iter().any(|engine| path.contains(engine)) {
+        return Some(Kind::Container);
+    }
+
+    // A runtime the user installed (Homebrew, nvm, ...) is most likely running
+    // their own code; the system one is left to the port fallback
+    let interpreter = command.split_whitespace().next().unwrap_or("");
+    let runtime = process.is_some_and(|p| is_runtime(&p.to_lowercase()))
+        || is_runtime(interpreter.rsplit(['/', '\\']).next().unwrap_or(""));
+    let binary = if path.is_empty() { interpreter } else { path.as_str() };
+    if runtime && !is_system_path(binary) {
+        return Some(Kind::Dev);
+    }
+    None
+}
+
+#[cfg(unix)]
+use nix::sys::signal::{kill, Signal};
+#[cfg(unix)]
+use nix::unistd::Pid;
+use std::{thread, time::Duration};
+
+/// True when an environment variable is set to anything but "", "0" or "false"
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| !matches!(v.trim(), "" | "0" | "false"))
+}
+
+#[cfg(not(feature = "banner"))]
+fn print_banner(_colors: bool) {}
+
+/// The banner goes to stderr so redirected or `--output` files stay clean
+#[cfg(feature = "banner")]
+fn print_banner(colors: bool) {
+    const BANNER: &str = include_str!("../banner.txt");
+
+    if colors {
+        // Rainbow colors for each line
+        let rainbow_colors = [
+            Color::Red,
+            Color::Yellow,
+            Color::Green,
+            Color::Cyan,
+            Color::Blue,
+            Color::Magenta,
+        ];
+
+        for (i, line) in BANNER.lines().enumerate() {
+            let color = rainbow_colors[i % rainbow_colors.len()];
+            let colored_line = match color {
+                Color::Red => format!("\x1b[31m{}\x1b[0m", line),
+                Color::Yellow => format!("\x1b[33m{}\x1b[0m", line),
+                Color::Green => format!("\x1b[32m{}\x1b[0m", line),
+                Color::Cyan => format!("\x1b[36m{}\x1b[0m", line),
+                Color::Blue => format!("\x1b[34m{}\x1b[0m", line),
+                Color::Magenta => format!("\x1b[35m{}\x1b[0m", line),
+                _ => line.to_string(),
+            };
+            eprintln!("{}", colored_line);
+        }
+    } else {
+        eprintln!("{}", BANNER);
+    }
+}
+
+#[cfg(unix)]
+fn kill_pid(pid: u32, signal: Option<i32>) -> anyhow::Result<()> {
+    // 0 and negative PIDs signal whole process groups, or every process of the user
+    let pid = match i32::try_from(pid) {
+        Ok(raw) if raw > 0 => Pid::from_raw(raw),
+        _ => return Err(anyhow::anyhow!("invalid PID {pid}")),
+    };
+    if let Some(signal) = signal {
+        kill(pid, Signal::try_from(signal)?)?;
+        return Ok(());
+    }
+
+    kill(pid, Signal::SIGTERM)?;
+    thread::sleep(Duration::from_millis(300));
+
+    if kill(pid, None).is_ok() {
+        kill(pid, Signal::SIGKILL)?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn kill_pid(pid: u32, _signal: Option<i32>) -> anyhow::Result<()> {
+    use std::process::Command;
+
+    // Ask the process to close first (like SIGTERM), then force it (like SIGKILL)
+    let _ = Command::new("taskkill")
+        .args(["/PID", &pid.to_string()])
+        .output_within();
+    thread::sleep(Duration::from_millis(300));
+
+    if windows_pid_alive(pid) {
+        let output = Command::new("taskkill")
+            .args(["/F", "/PID", &pid.to_string()])
+            .output_within()
+            .context("failed to run taskkill")?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn pid_alive(pid: u32) -> bool {
+    let Some(pid) = i32::try_from(pid).ok().filter(|raw| *raw > 0) else {
+        return false;
+    };
+    // EPERM means the process exists but belongs to someone else
+    !matches!(kill(Pid::from_raw(pid), None), Err(nix::errno::Errno::ESRCH))
+}
+
+#[cfg(windows)]
+fn pid_alive(pid: u32) -> bool {
+    windows_pid_alive(pid)
+}
+
+/// Name of a process found by PID alone, without a listening socket to go by
+#[cfg(target_os = "macos")]
+fn process_name(pid: u32) -> Option<String> {
+    get_process_name_libproc(pid)
+}
+
+#[cfg(windows)]
+fn process_name(pid: u32) -> Option<String> {
+    get_process_names_windows().remove(&pid)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn process_name(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Owner of a process found by PID alone
+#[cfg(unix)]
+fn process_uid(pid: u32) -> Option<u32> {
+    use std::process::Command;
+
+    let output = Command::new("ps").args(["-o", "uid=", "-p", &pid.to_string()]).output_within().ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(not(unix))]
+fn process_uid(_pid: u32) -> Option<u32> {
+    None
+}
+
+/// Accept a signal by name (`TERM`, `SIGTERM`, case-insensitive) or number
+#[cfg(unix)]
+fn parse_signal(value: &str) -> Result<i32, String> {
+    let upper = value.to_uppercase();
+    let name = if upper.starts_with("SIG") { upper } else { format!("SIG{upper}") };
+    Signal::iterator()
+        .find(|sig| sig.as_str() == name || value.parse::<i32>() == Ok(*sig as i32))
+        .map(|sig| sig as i32)
+        .ok_or_else(|| format!("unknown signal '{value}'; see --list-signals"))
+}
+
+#[cfg(not(unix))]
+fn parse_signal(_value: &str) -> Result<i32, String> {
+    Err("signals are not supported on this platform; porty uses taskkill".to_string())
+}
+
+#[cfg(unix)]
+fn print_signals() {
+    for sig in Signal::iterator() {
+        println!("{:>2} {}", sig as i32, sig.as_str().trim_start_matches("SIG"));
+    }
+}
+
+#[cfg(not(unix))]
+fn print_signals() {
+    println!("Signals are not supported on this platform; porty uses taskkill");
+}
+
+fn main() {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if cli.machine {
+        apply_machine(&mut cli, &matches);
+    }
+    if let Some(timeout) = cli.max_time {
+        let _ = TIMEOUT.set(timeout);
+        restart_deadline();
+    }
+    if let Some(ref path) = cli.config {
+        let _ = CONFIG_PATH.set(path.clone());
+    }
+
+    if (cli.count || cli.plain_pids)
+        && !matches!(
+            cli.cmd,
+            None | Some(Cmd::All | Cmd::Dev | Cmd::Prod | Cmd::Find { .. } | Cmd::Render { .. })
+        )
+    {
+        let flag = if cli.count { "--count" } else { "--plain-pids" };
+        eprintln!("{flag} only applies to listings (all, dev, prod, find, render)");
+        std::process::exit(1);
+    }
+
+    // Informational only, no need to scan ports
+    if let Some(Cmd::Kill { list_signals: true, .. }) = cli.cmd {
+        print_signals();
+        return;
+    }
+
+    // Runs its own config check, so it must come before loading it
+    if let Some(Cmd::Doctor) = cli.cmd {
+        let healthy = cmd_doctor(cli.config.as_deref(), cli.colors);
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+
+    // Notes only touch the notes file, no need to scan ports
+    if let Some(Cmd::Note { port, ref text, clear }) = cli.cmd {
+        if let Err(e) = cmd_note(port, text.as_deref(), clear) {
+            eprintln!("note error: {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut config = load_config(cli.config.as_deref()).unwrap_or_else(|e| {
+        eprintln!("config error: {e:#}");
+        std::process::exit(1);
+    });
+    if let Some(retries) = cli.retry {
+        config.discovery.retries = retries;
+    }
+    config.discovery.debug = cli.debug;
+    config.discovery.no_enrich = cli.no_enrich;
+
+    // Acting on stale data is dangerous, so free/kill always rescan
+    let use_cache = !matches!(cli.cmd, Some(Cmd::Free { .. }) | Some(Cmd::Kill { .. }));
+    let cache_config = cli.cache.and_then(|_| config_in_use(cli.config.as_deref()));
+    let cached = match cli.cache {
+        Some(ttl) if use_cache => read_cache(ttl, cache_config.as_deref()),
+        _ => None,
+    };
+
+    // Shared by discovery and the detailed view so docker runs at most once
+    let docker = DockerSnapshot::new(!cli.no_docker);
+    let mut discovery_error = None;
+    let entries = if let Some(Cmd::Render { ref input }) = cli.cmd {
+        read_snapshot(input).unwrap_or_else(|e| {
+            eprintln!("render error: {e:#}");
+            std::process::exit(1);
+        })
+    } else if let Some(entries) = cached {
+        entries
+    } else {
+        let entries = discover_ports(&config, &docker).unwrap_or_else(|e| {
+            discovery_error = Some(format!("{e:#}"));
+            vec![]
+        });
+        // A --no-enrich, --no-docker or timed-out scan would hand later full
+        // runs its bare rows
+        if cli.cache.is_some()
+            && discovery_error.is_none()
+            && !cli.no_enrich
+            && !cli.no_docker
+            && !timed_out()
+        {
+            write_cache(&entries, cache_config.as_deref());
+        }
+        entries
+    };
+    // A saved listing came from another porty run, maybe on another machine
+    let entries = if cli.include_self || matches!(cli.cmd, Some(Cmd::Render { .. })) {
+        entries
+    } else {
+        filter_self(entries)
+    };
+    warn_shared_ports(&entries);
+    let table_opts = TableOptions::from_cli(&cli, &config);
+    // Keep stdout parseable for machine-readable formats
+    let banner = table_opts.format == OutputFormat::Table
+        && !cli.quiet
+        && !cli.count
+        && !cli.plain_pids
+        && config.banner
+        && !env_flag("PORTY_NO_BANNER");
+
+    let mut out: Box<dyn Write> = match cli.output {
+        Some(ref path) => open_output(path).unwrap_or_else(|e| {
+            eprintln!("output error: {e:#}");
+            std::process::exit(1);
+        }),
+        None if use_pager(&cli, &table_opts) => Box::new(Pager::default()),
+        None => Box::new(std::io::stdout()),
+    };
+
+    if let Some(error) = discovery_error {
+        // An empty array would read as "no ports", so JSON consumers get the error instead
+        if matches!(table_opts.format, OutputFormat::Json | OutputFormat::Ndjson) {
+            let error = serde_json::json!({ "schema": SNAPSHOT_SCHEMA, "error": error });
+            let _ = if table_opts.format == OutputFormat::Json {
+                print_json(&error, table_opts.pretty, &mut out)
+            } else {
+                print_json_line(&error, &mut out)
+            };
+            drop(out);
+            std::process::exit(1);
+        }
+        eprintln!("discovery error: {error}");
+    }
+
+    let success = match cli.cmd {
+        None => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            let filtered = match config.default_command {
+                Some(DefaultCommand::All) => entries,
+                Some(DefaultCommand::Dev) => filter_dev(&entries),
+                Some(DefaultCommand::Prod) => filter_prod(&entries),
+                None => filter_default(&entries),
+            };
+            cmd_list(filtered, &docker, &cli, &table_opts, &mut out)
+        }
+        Some(Cmd::All) => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            cmd_list(entries, &docker, &cli, &table_opts, &mut out)
+        }
+        Some(Cmd::Dev) => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            let filtered = filter_dev(&entries);
+            cmd_list(filtered, &docker, &cli, &table_opts, &mut out)
+        }
+        Some(Cmd::Prod) => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            let filtered = filter_prod(&entries);
+            cmd_list(filtered, &docker, &cli, &table_opts, &mut out)
+        }
+        Some(Cmd::Port { ref ports, watch, interval, interval_jitter, ref watch_file, max_size, time_format, traffic, resolve_dns, .. }) => {
+            let detail_opts = DetailOptions { traffic, debug: cli.debug, time_format, resolve_dns };
+            if watch {
+                if cli.output.is_some() {
+                    eprintln!("--output can't be combined with --watch");
+                    std::process::exit(1);
+                }
+                let [port] = ports[..] else {
+                    eprintln!("--watch takes a single port");
+                    std::process::exit(1);
+                };
+                let watch = PortWatch {
+                    port,
+                    schedule: WatchSchedule {
+                        interval: Duration::from_secs(interval),
+                        jitter: interval_jitter.unwrap_or_default(),
+                    },
+                    log: watch_file.clone().map(|path| WatchLog { path, max_size }),
+                    include_self: cli.include_self,
+                    all_users: cli.all_users,
+                };
+                let entries = if cli.all_users { entries } else { filter_own_user(entries) };
+                watch_port(&config, entries, docker, &watch, &table_opts, &detail_opts);
+                true
+            } else {
+                if banner {
+                    print_banner(cli.colors);
+                }
+                match ports[..] {
+                    [port] => cmd_port(&entries, &docker, port, &table_opts, &detail_opts, &mut out),
+                    _ => cmd_ports(&entries, &docker, ports, &table_opts, &detail_opts, &mut out),
+                }
+            }
+        }
+        Some(Cmd::Compare { first, second }) => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            let detail_opts = DetailOptions { debug: cli.debug, ..DetailOptions::default() };
+            cmd_compare(&entries, &docker, (first, second), &table_opts, &detail_opts, &mut out)
+        }
+        Some(Cmd::Export { ref path }) => {
+            let entries = if cli.all_users { entries } else { filter_own_user(entries) };
+            let detail_opts = DetailOptions { debug: cli.debug, ..DetailOptions::default() };
+            match cmd_export(&entries, &docker, path, &table_opts, &detail_opts) {
+                Ok(()) => {
+                    let _ = writeln!(out, "Wrote report on {} ports to {}", entries.len(), path.display());
+                    true
+                }
+                Err(e) => {
+                    eprintln!("export error: {e:#}");
+                    false
+                }
+            }
+        }
+        Some(Cmd::Find { ref query }) => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            let filtered = filter_find(&entries, query);
+            cmd_list(filtered, &docker, &cli, &table_opts, &mut out)
+        }
+        Some(Cmd::Scan { range, open, timeout, addr }) => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            let scan = ScanOptions { range, open_only: open, timeout, addr };
+            cmd_scan(&entries, &config, &scan, &table_opts, &mut out);
+            true
+        }
+        Some(Cmd::Render { .. }) => {
+            if banner {
+                print_banner(cli.colors);
+            }
+            cmd_list(entries, &docker, &cli, &table_opts, &mut out)
+        }
+        Some(Cmd::Tui { interval }) => {
+            if cli.output.is_some() {
+                eprintln!("--output can't be combined with tui");
+                std::process::exit(1);
+            }
+            let entries = if cli.all_users { entries } else { filter_own_user(entries) };
+            if let Err(e) = cmd_tui(&config, entries, !cli.no_docker, cli.all_users, cli.include_self, Duration::from_secs(interval)) {
+                eprintln!("tui error: {e:#}");
+                std::process::exit(1);
+            }
+            true
+        }
+        Some(Cmd::Free { port, addr }) => {
+            cmd_free(&entries, port, addr);
+            true
+        }
+        Some(Cmd::Kill { port, pid, kind, name, force, signal, wait, .. }) => {
+            let target = KillTarget { port, pid, kind, name, all_users: cli.all_users };
+            cmd_kill(&entries, &target, force, signal, wait, &table_opts, &mut out)
+        }
+        Some(Cmd::Doctor) | Some(Cmd::Note { .. }) => unreachable!("handled before discovery"),
+    };
+
+    if !success {
+        drop(out); // exit() skips destructors, and the pager shows output on drop
+        std::process::exit(1);
+    }
+}
+
+/// Expand `--machine` into the flags it stands for. Output flags given after
+/// it on the command line win, e.g. `--machine --format json`.
+fn apply_machine(cli: &mut Cli, matches: &ArgMatches) {
+    let Some(pos) = arg_index(matches, "machine") else {
+        return;
+    };
+    let given = |ids: &[&str]| ids.iter().any(|id| arg_index(matches, id).is_some_and(|i| i > pos));
+
+    cli.quiet = true;
+    if !given(&["colors"]) {
+        cli.colors = false;
+    }
+    if !given(&["width", "wide"]) {
+        cli.width = None;
+        cli.wide = true;
+    }
+    if !given(&["format", "json", "json_pretty"]) {
+        cli.format = OutputFormat::Compact;
+        cli.json = false;
+        cli.json_pretty = false;
+    }
+}
+
+/// Position on the command line of the last occurrence of global arg `id`, which
+/// may have been given before or after the subcommand
+fn arg_index(matches: &ArgMatches, id: &str) -> Option<usize> {
+    let here = (matches.value_source(id) == Some(ValueSource::CommandLine))
+        .then(|| matches.indices_of(id).and_then(|indices| indices.max()))
+        .flatten();
+    let below = matches.subcommand().and_then(|(_, sub)| arg_index(sub, id));
+    here.max(below)
+}
+
+/// Version of the `--format json` listing envelope; bump it when `PortEntry`
+/// changes in a way older readers can't parse
+const SNAPSHOT_SCHEMA: u32 = 1;
+
+/// `--format json` listing output, which `render` reads back
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    schema: u32,
+    ports: Vec<PortEntry>,
+}
+
+/// Load a listing saved with `--format json` or `--format ndjson` (one entry
+/// per line). `-` reads stdin.
+fn read_snapshot(path: &Path) -> Result<Vec<PortEntry>> {
+    use std::io::Read;
+
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).context("failed to read stdin")?;
+        text
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?
+    };
+
+    let name = if path == Path::new("-") { "stdin".to_string() } else { path.display().to_string() };
+    parse_snapshot(&name, &text)
+}
+
+fn parse_snapshot(name: &str, text: &str) -> Result<Vec<PortEntry>> {
+    let mismatch = || {
+        format!(
+            "{name} is not a porty port listing \
+             (expected the output of --format json or --format ndjson)"
+        )
+    };
+
+    let text = text.trim_start();
+    // Bare array written before listings carried a schema
+    if text.starts_with('[') {
+        return serde_json::from_str(text).with_context(mismatch);
+    }
+
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(text) {
+        if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+            return Err(anyhow::anyhow!("{name} records a failed scan: {error}"));
+        }
+        if let Some(schema) = value.get("schema") {
+            if schema.as_u64() != Some(SNAPSHOT_SCHEMA.into()) {
+                return Err(anyhow::anyhow!(
+                    "{name} was saved with snapshot schema {schema}, but this porty reads schema {SNAPSHOT_SCHEMA}"
+                ));
+            }
+            let snapshot: Snapshot = serde_json::from_value(value).with_context(mismatch)?;
+            return Ok(snapshot.ports);
+        }
+        // The detailed `port` view is a single object with a different shape
+        if value.get("process_name").is_some() {
+            return Err(anyhow::anyhow!(
+                "{name} holds `porty port` details; render only takes port listings"
+            ));
+        }
+    }
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("{} (line {})", mismatch(), i + 1))
+        })
+        .collect()
+}
+
+fn open_output(path: &Path) -> Result<Box<dyn Write>> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    Ok(Box::new(std::io::BufWriter::new(file)))
+}
+
+/// Page only tables going to a terminal; pipes, files and `--watch` stay as-is
+fn use_pager(cli: &Cli, opts: &TableOptions) -> bool {
+    use std::io::IsTerminal;
+
+    !cli.no_pager
+        && opts.format == OutputFormat::Table
+        && !matches!(cli.cmd, Some(Cmd::Port { watch: true, .. }))
+        && std::io::stdout().is_terminal()
+}
+
+/// Collects stdout and, once dropped, sends it through `$PAGER` (default `less`)
+/// if it's taller than the terminal, or straight to stdout otherwise
+#[derive(Default)]
+struct Pager {
+    buf: Vec<u8>,
+}
+
+impl Write for Pager {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let rows = crossterm::terminal::size().map_or(usize::MAX, |(_, rows)| rows as usize);
+        let lines = self.buf.iter().filter(|&&b| b == b'\n').count();
+        if lines >= rows && run_pager(&self.buf).is_ok() {
+            return;
+        }
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(&self.buf).and_then(|_| stdout.flush());
+    }
+}
+
+fn run_pager(text: &[u8]) -> std::io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less".to_string());
+    let mut parts = pager.split_whitespace();
+    let mut cmd = Command::new(parts.next().unwrap_or("less"));
+    cmd.args(parts).stdin(Stdio::piped());
+    // Same defaults as git: keep colors, and don't clear the screen on exit
+    if std::env::var_os("LESS").is_none() {
+        cmd.env("LESS", "FRX");
+    }
+
+    let mut child = cmd.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(text) {
+            // Quitting the pager early closes the pipe
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Ports held by more than one process (e.g. via SO_REUSEPORT), with their PIDs
+fn shared_ports(entries: &[PortEntry]) -> std::collections::BTreeMap<u16, Vec<u32>> {
+    let mut pids_by_port: std::collections::BTreeMap<u16, Vec<u32>> = std::collections::BTreeMap::new();
+    for entry in entries {
+        if let Some(pid) = entry.pid {
+            let pids = pids_by_port.entry(entry.port).or_default();
+            if !pids.contains(&pid) {
+                pids.push(pid);
+            }
+        }
+    }
+    pids_by_port.retain(|_, pids| pids.len() > 1);
+    pids_by_port
+}
+
+/// A stale server sharing a port with a fresh one is easy to miss, so call it out
+fn warn_shared_ports(entries: &[PortEntry]) {
+    for (port, pids) in shared_ports(entries) {
+        let pids = pids.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ");
+        eprintln!("warning: port {port} has multiple listening processes (PIDs {pids})");
+    }
+}
+
+/// Container listeners that no running container publishes. Usually a stopped
+/// container left a stale vmnet mapping, or it's really a host process.
+fn warn_unmapped_containers(entries: &[PortEntry], docker: &DockerSnapshot) {
+    if !entries.iter().any(|e| e.kind == Kind::Container) || !docker.enabled || !docker_on_path() {
+        return;
+    }
+    for entry in entries.iter().filter(|e| e.kind == Kind::Container) {
+        if !docker.containers().iter().any(|c| c.publishes(entry.port)) {
+            eprintln!(
+                "note: port {} looks like a container but no running container maps to it \
+                 (a stopped container may have left a stale mapping, or it's a host process)",
+                entry.port
+            );
+        }
+    }
+}
+
+/// Accepts "2s", "500ms", "1m", or a bare number of seconds
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}' (expected e.g. 2s or 500ms)"))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        _ => Err(format!("invalid duration unit in '{value}' (use ms, s or m)")),
+    }
+}
+
+/// Parse one --sort key, e.g. `port` or `-mem`
+fn parse_sort_spec(value: &str) -> Result<SortSpec, String> {
+    let (name, descending) = match value.trim().strip_prefix('-') {
+        Some(name) => (name, true),
+        None => (value.trim(), false),
+    };
+    let key = SortKey::from_str(name, true).map_err(|_| {
+        let keys: Vec<String> = SortKey::value_variants()
+            .iter()
+            .filter_map(|k| k.to_possible_value().map(|v| v.get_name().to_string()))
+            .collect();
+        format!("unknown sort key '{name}' (expected {}, optionally prefixed with -)", keys.join(", "))
+    })?;
+    Ok(SortSpec { key, descending })
+}
+
+/// Parse a file size like `4096`, `512KB` or `10MB` (units of 1024 bytes)
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, ""),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{value}' (expected e.g. 512KB or 10MB)"))?;
+    let scale: u64 = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => return Err(format!("invalid size unit in '{value}' (use KB, MB or GB)")),
+    };
+    Ok(number.saturating_mul(scale))
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    /// Milliseconds since the Unix epoch when the snapshot was taken
+    created_ms: u64,
+    /// Config file whose rules classified the entries, if any
+    config: Option<PathBuf>,
+    entries: Vec<PortEntry>,
+}
+
+/// The config file a run classifies with, which cached rows depend on
+fn config_in_use(explicit: Option<&Path>) -> Option<PathBuf> {
+    let path = match explicit {
+        Some(path) => path.to_path_buf(),
+        None => default_config_path()?,
+    };
+    // Canonical, so `--config x.toml` from another directory doesn't match
+    std::fs::canonicalize(path).ok()
+}
+
+fn cache_path() -> PathBuf {
+    // Per-user file so cached results never leak between accounts
+    #[cfg(unix)]
+    let name = format!("porty-cache-{}.json", unsafe { libc::getuid() });
+    #[cfg(not(unix))]
+    let name = "porty-cache.json".to_string();
+    std::env::temp_dir().join(name)
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn read_cache(ttl: Duration, config: Option<&Path>) -> Option<Vec<PortEntry>> {
+    let text = std::fs::read_to_string(cache_path()).ok()?;
+    let cache: CacheFile = serde_json::from_str(&text).ok()?;
+    // Another config's rules would have classified the ports differently
+    if cache.config.as_deref() != config {
+        return None;
+    }
+    let age = now_ms().saturating_sub(cache.created_ms);
+    (age <= ttl.as_millis() as u64).then_some(cache.entries)
+}
+
+fn write_cache(entries: &[PortEntry], config: Option<&Path>) {
+    let cache = CacheFile {
+        created_ms: now_ms(),
+        config: config.map(Path::to_path_buf),
+        entries: entries.to_vec(),
+    };
+    // Caching is best-effort; a failed write just means the next run rescans
+    if let Ok(json) = serde_json::to_string(&cache) {
+        let _ = std::fs::write(cache_path(), json);
+    }
+}
+
+/// Render a listing. Returns `false` when it includes a `--fail-on` category.
+fn cmd_list(
+    mut entries: Vec<PortEntry>,
+    docker: &DockerSnapshot,
+    cli: &Cli,
+    opts: &TableOptions,
+    out: &mut dyn Write,
+) -> bool {
+    if !cli.all_users {
+        entries = filter_own_user(entries);
+    }
+    if let Some(ref bind) = cli.bind {
+        entries = filter_bind(entries, bind);
+    }
+    if let Some(ref pattern) = cli.process_filter {
+        entries = filter_process(entries, pattern, cli.filter_exec_path);
+    }
+    // A saved listing's containers aren't the ones running here
+    if cli.verbose && !matches!(cli.cmd, Some(Cmd::Render { .. })) {
+        warn_unmapped_containers(&entries, docker);
+    }
+    if cli.check || opts.wants(Column::Health) {
+        check_health(&mut entries);
+    }
+    if cli.connections || sorts_by(&cli.sort, SortKey::Connections) || opts.wants(Column::Conns) {
+        let counts = count_established_by_port();
+        for entry in entries.iter_mut() {
+            entry.connections = Some(counts.get(&entry.port).copied().unwrap_or(0));
+        }
+    }
+    if cli.services || opts.wants(Column::Service) {
+        for entry in entries.iter_mut() {
+            entry.service = service_name(entry.port).map(str::to_string);
+        }
+    }
+    if opts.wants(Column::Mem) || sorts_by(&cli.sort, SortKey::Mem) {
+        let pids: Vec<u32> = entries.iter().filter_map(|e| e.pid).collect();
+        let usage = get_resource_usage(&pids);
+        for entry in entries.iter_mut() {
+            let (memory_kb, cpu_percent) = entry.pid.and_then(|pid| usage.get(&pid).copied()).unzip();
+            entry.memory_kb = memory_kb;
+            entry.cpu_percent = cpu_percent;
+        }
+    }
+    if cli.cwd || opts.wants(Column::Cwd) {
+        let pids: Vec<u32> = entries.iter().filter_map(|e| e.pid).collect();
+        let dirs = get_working_dirs(&pids);
+        for entry in entries.iter_mut() {
+            entry.cwd = entry.pid.and_then(|pid| dirs.get(&pid).cloned());
+        }
+    }
+    sort_entries(&mut entries, &cli.sort);
+    apply_notes(&mut entries);
+
+    let project_dir = cli.project_dir.clone().or_else(|| std::env::current_dir().ok());
+    if let Some(dir) = project_dir {
+        let expected = project_ports(&dir);
+        for entry in entries.iter_mut() {
+            entry.project = expected.contains(&entry.port);
+        }
+    }
+
+    let violations: Vec<String> = entries
+        .iter()
+        .filter(|e| cli.fail_on.contains(&e.kind))
+        .map(|e| match (&e.process, e.pid) {
+            (Some(process), Some(pid)) => format!("{} ({}, {} PID {})", e.port, format_kind_id(e.kind), process, pid),
+            _ => format!("{} ({})", e.port, format_kind_id(e.kind)),
+        })
+        .collect();
+
+    if cli.dedupe_by == Some(DedupeKey::Process) {
+        entries = dedupe_by_process(entries);
+    }
+
+    if cli.count {
+        let _ = writeln!(out, "{}", entries.len());
+    } else if cli.plain_pids {
+        let mut seen = std::collections::HashSet::new();
+        for pid in entries.iter().filter_map(|e| e.pid) {
+            if seen.insert(pid) {
+                let _ = writeln!(out, "{pid}");
+            }
+        }
+    } else {
+        render(Output::Listing(entries), opts, out);
+    }
+
+    if !violations.is_empty() {
+        eprintln!("--fail-on matched {} port{}:", violations.len(), if violations.len() == 1 { "" } else { "s" });
+        for violation in &violations {
+            eprintln!("  {violation}");
+        }
+    }
+    violations.is_empty()
+}
+
+/// One row per process: later rows of the same PID add their ports and bind
+/// addresses to its first row. Rows without a PID are kept as they are.
+fn dedupe_by_process(entries: Vec<PortEntry>) -> Vec<PortEntry> {
+    let mut rows: Vec<PortEntry> = Vec::new();
+    let mut index_by_pid: std::collections::HashMap<u32, usize> = std::collections::HashMap::new();
+    for entry in entries {
+        let Some(&idx) = entry.pid.and_then(|pid| index_by_pid.get(&pid)) else {
+            if let Some(pid) = entry.pid {
+                index_by_pid.insert(pid, rows.len());
+            }
+            rows.push(entry);
+            continue;
+        };
+        let row = &mut rows[idx];
+        row.other_ports.push(entry.port);
+        for addr in entry.addresses {
+            if !row.addresses.contains(&addr) {
+                row.addresses.push(addr);
+            }
+        }
+        row.project |= entry.project;
+        row.connections = match (row.connections, entry.connections) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        if row.note.is_none() {
+            row.note = entry.note;
+        }
+    }
+    rows
+}
+
+/// What a command wants to show, independent of the output format
+enum Output {
+    Listing(Vec<PortEntry>),
+    Detail(Box<DetailedPortInfo>),
+    /// Two ports side by side
+    Compare(Box<[DetailedPortInfo; 2]>),
+    NotFound(u16),
+}
+
+fn render(output: Output, opts: &TableOptions, out: &mut dyn Write) {
+    match write_output(output, opts, out).and_then(|_| out.flush()) {
+        // e.g. piped into `head`
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+        Err(e) => eprintln!("failed to write output: {e}"),
+        Ok(()) => {}
+    }
+}
+
+fn write_output(output: Output, opts: &TableOptions, out: &mut dyn Write) -> std::io::Result<()> {
+    match (opts.format, output) {
+        (OutputFormat::Table, Output::Listing(entries)) => print_table(entries, opts, out),
+        (OutputFormat::Table, Output::Detail(info)) => print_detailed_port_info(&info, opts.colors, &opts.thresholds, opts.full, out),
+        (OutputFormat::Table, Output::Compare(pair)) => print_compare(&pair, opts, out),
+        (OutputFormat::Table, Output::NotFound(port)) => writeln!(out, "No listener found on port {port}"),
+
+        (OutputFormat::Json, Output::Listing(entries)) => {
+            print_json(&Snapshot { schema: SNAPSHOT_SCHEMA, ports: entries }, opts.pretty, out)
+        }
+        (OutputFormat::Json, Output::Detail(info)) => print_json(&info, opts.pretty, out),
+        (OutputFormat::Json, Output::Compare(pair)) => print_json(&*pair, opts.pretty, out),
+        (OutputFormat::Json, Output::NotFound(_)) => writeln!(out, "null"),
+
+        (OutputFormat::Ndjson, Output::Listing(entries)) => {
+            for entry in &entries {
+                print_json_line(entry, out)?;
+            }
+            Ok(())
+        }
+        (OutputFormat::Ndjson, Output::Detail(info)) => print_json_line(&info, out),
+        (OutputFormat::Ndjson, Output::Compare(pair)) => {
+            for info in pair.iter() {
+                print_json_line(info, out)?;
+            }
+            Ok(())
+        }
+        (OutputFormat::Ndjson, Output::NotFound(_)) => Ok(()),
+
+        (OutputFormat::Csv, Output::Listing(entries)) => print_csv(&entries, out),
+        (OutputFormat::Csv, Output::Detail(info)) => print_detailed_csv(&[&info], out),
+        (OutputFormat::Csv, Output::Compare(pair)) => print_detailed_csv(&[&pair[0], &pair[1]], out),
+        (OutputFormat::Csv, Output::NotFound(_)) => print_csv(&[], out),
+
+        (OutputFormat::Compact, Output::Listing(entries)) => print_compact(&entries, opts, out),
+        (OutputFormat::Compact, Output::Detail(info)) => {
+            let exec_path = info.exec_path.as_deref().filter(|_| opts.verbose);
+            print_compact_line(info.port, Some(info.pid), info.kind, &info.process_name, exec_path, opts.colors, out)
+        }
+        (OutputFormat::Compact, Output::Compare(pair)) => {
+            for info in pair.iter() {
+                let exec_path = info.exec_path.as_deref().filter(|_| opts.verbose);
+                print_compact_line(info.port, Some(info.pid), info.kind, &info.process_name, exec_path, opts.colors, out)?;
+            }
+            Ok(())
+        }
+        (OutputFormat::Compact, Output::NotFound(_)) => Ok(()),
+
+        (OutputFormat::Tsv, Output::Listing(entries)) => {
+            print_tsv_header(out)?;
+            for e in &entries {
+                print_tsv_line(e.port, e.process.as_deref(), e.pid, e.kind, e.exec_path.as_deref(), out)?;
+            }
+            Ok(())
+        }
+        (OutputFormat::Tsv, Output::Detail(info)) => {
+            print_tsv_header(out)?;
+            print_tsv_line(info.port, Some(&info.process_name), Some(info.pid), info.kind, info.exec_path.as_deref(), out)
+        }
+        (OutputFormat::Tsv, Output::Compare(pair)) => {
+            print_tsv_header(out)?;
+            for info in pair.iter() {
+                print_tsv_line(info.port, Some(&info.process_name), Some(info.pid), info.kind, info.exec_path.as_deref(), out)?;
+            }
+            Ok(())
+        }
+        (OutputFormat::Tsv, Output::NotFound(_)) => print_tsv_header(out),
+
+        (OutputFormat::Prometheus, Output::Listing(entries)) => print_prometheus(&entries, out),
+        (OutputFormat::Prometheus, Output::Detail(info)) => print_detailed_prometheus(&[&info], out),
+        (OutputFormat::Prometheus, Output::Compare(pair)) => print_detailed_prometheus(&[&pair[0], &pair[1]], out),
+        (OutputFormat::Prometheus, Output::NotFound(port)) => {
+            print_listening_header(out)?;
+            writeln!(out, "porty_port_listening{{port=\"{port}\"}} 0")
+        }
+    }
+}
+
+fn print_json_line<T: Serialize + ?Sized>(value: &T, out: &mut dyn Write) -> std::io::Result<()> {
+    match serde_json::to_string(value) {
+        Ok(json) => writeln!(out, "{json}")?,
+        Err(e) => eprintln!("failed to serialize JSON: {e}"),
+    }
+    Ok(())
+}
+
+fn print_compact(entries: &[PortEntry], opts: &TableOptions, out: &mut dyn Write) -> std::io::Result<()> {
+    for e in entries {
+        let exec_path = if opts.verbose { Some(e.exec_path.as_deref().unwrap_or("-")) } else { None };
+        print_compact_line(e.port, e.pid, e.kind, e.process.as_deref().unwrap_or("-"), exec_path, opts.colors, out)?;
+    }
+    Ok(())
+}
+
+/// With colors, a dot in the table's category color leads the line so
+/// categories still stand out
+fn print_compact_line(
+    port: u16,
+    pid: Option<u32>,
+    kind: Kind,
+    process: &str,
+    exec_path: Option<&str>,
+    colors: bool,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    if colors {
+        write!(out, "{}●\x1b[0m ", ansi_fg(get_kind_color(kind)))?;
+    }
+    let pid = pid.map(|p| p.to_string()).unwrap_or("-".into());
+    write!(out, "{} {} {} ", port, pid, format_kind_id(kind))?;
+    // PROCESS goes last so names like `redis (container)` can keep their
+    // spaces; the exec path before it escapes its own so it stays one field
+    if let Some(path) = exec_path {
+        write!(out, "{} ", path.replace('%', "%25").replace(' ', "%20"))?;
+    }
+    writeln!(out, "{}", process.replace(['\n', '\r'], " "))
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn print_csv(entries: &[PortEntry], out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, "port,pid,process,kind,addresses,exec_path,health")?;
+    for e in entries {
+        let fields = [
+            e.port.to_string(),
+            e.pid.map(|p| p.to_string()).unwrap_or_default(),
+            e.process.clone().unwrap_or_default(),
+            format_kind_id(e.kind).to_string(),
+            e.addresses.iter().map(|(_, a)| a.as_str()).collect::<Vec<_>>().join(" "),
+            e.exec_path.clone().unwrap_or_default(),
+            e.health.map(|h| format_health(Some(h)).to_lowercase()).unwrap_or_default(),
+        ];
+        writeln!(out, "{}", fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","))?;
+    }
+    Ok(())
+}
+
+fn print_tsv_header(out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, "port\tprocess\tpid\tkind\texec_path")
+}
+
+fn print_tsv_line(
+    port: u16,
+    process: Option<&str>,
+    pid: Option<u32>,
+    kind: Kind,
+    exec_path: Option<&str>,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    // TSV has no quoting, so tabs and line breaks inside a value become spaces
+    let field = |value: &str| value.replace(['\t', '\n', '\r'], " ");
+    writeln!(
+        out,
+        "{}\t{}\t{}\t{}\t{}",
+        port,
+        field(process.unwrap_or_default()),
+        pid.map(|p| p.to_string()).unwrap_or_default(),
+        format_kind_id(kind),
+        field(exec_path.unwrap_or_default()),
+    )
+}
+
+const DETAILED_CSV_HEADER: &str =
+    "port,pid,process,kind,user,command,working_dir,uptime,memory_rss_kb,cpu_percent,threads,file_descriptors,active_connections";
+
+fn print_detailed_csv(infos: &[&DetailedPortInfo], out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, "{DETAILED_CSV_HEADER}")?;
+    for info in infos {
+        print_detailed_csv_row(info, out)?;
+    }
+    Ok(())
+}
+
+fn print_detailed_csv_row(info: &DetailedPortInfo, out: &mut dyn Write) -> std::io::Result<()> {
+    let fields = [
+        info.port.to_string(),
+        info.pid.to_string(),
+        info.process_name.clone(),
+        format_kind_id(info.kind).to_string(),
+        info.user_name.clone(),
+        info.command.clone(),
+        info.working_dir.clone().unwrap_or_default(),
+        info.uptime.clone(),
+        info.memory_rss.to_string(),
+        format_float(info.cpu_usage, 1),
+        info.thread_count.to_string(),
+        info.file_descriptors.to_string(),
+        info.active_connections.to_string(),
+    ];
+    writeln!(out, "{}", fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","))
+}
+
+/// Escape a Prometheus label value
+fn prom_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn print_listening_header(out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, "# HELP porty_port_listening Whether a process is listening on the port")?;
+    writeln!(out, "# TYPE porty_port_listening gauge")
+}
+
+fn print_listening_sample(e: &PortEntry, out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out,
+        "porty_port_listening{{port=\"{}\",pid=\"{}\",process=\"{}\",kind=\"{}\"}} 1",
+        e.port,
+        e.pid.map(|p| p.to_string()).unwrap_or_default(),
+        prom_label(e.process.as_deref().unwrap_or("")),
+        format_kind_id(e.kind),
+    )
+}
+
+fn print_prometheus(entries: &[PortEntry], out: &mut dyn Write) -> std::io::Result<()> {
+    print_listening_header(out)?;
+    for e in entries {
+        print_listening_sample(e, out)?;
+    }
+    let probed: Vec<_> = entries.iter().filter(|e| e.health.is_some()).collect();
+    if !probed.is_empty() {
+        writeln!(out, "# HELP porty_port_up Whether the last health probe succeeded")?;
+        writeln!(out, "# TYPE porty_port_up gauge")?;
+        for e in probed {
+            let up = u8::from(e.health == Some(Health::Ok));
+            writeln!(out, "porty_port_up{{port=\"{}\"}} {}", e.port, up)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads one value out of the detailed view, for table-driven renderers
+type DetailField<T> = fn(&DetailedPortInfo) -> T;
+
+fn print_detailed_prometheus(infos: &[&DetailedPortInfo], out: &mut dyn Write) -> std::io::Result<()> {
+    let metrics: [(&str, &str, DetailField<f64>); 6] = [
+        ("porty_process_resident_memory_bytes", "Resident memory of the listening process", |i| (i.memory_rss * 1024) as f64),
+        ("porty_process_virtual_memory_bytes", "Virtual memory of the listening process", |i| (i.memory_virtual * 1024) as f64),
+        ("porty_process_cpu_percent", "CPU usage of the listening process", |i| i.cpu_usage),
+        ("porty_process_threads", "Thread count of the listening process", |i| i.thread_count as f64),
+        ("porty_process_open_fds", "Open file descriptors of the listening process", |i| i.file_descriptors as f64),
+        ("porty_port_active_connections", "Established connections on the port", |i| i.active_connections as f64),
+    ];
+    // Each metric's HELP/TYPE may appear only once, followed by all its samples
+    for (name, help, value) in metrics {
+        writeln!(out, "# HELP {name} {help}")?;
+        writeln!(out, "# TYPE {name} gauge")?;
+        for info in infos {
+            let labels = format!(
+                "port=\"{}\",pid=\"{}\",process=\"{}\",kind=\"{}\"",
+                info.port,
+                info.pid,
+                prom_label(&info.process_name),
+                format_kind_id(info.kind),
+            );
+            writeln!(out, "{name}{{{labels}}} {}", value(info))?;
+        }
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T, pretty: bool, out: &mut dyn Write) -> std::io::Result<()> {
+    // Going through `Value` sorts object keys, so runs diff cleanly
+    let json = if pretty {
+        serde_json::to_value(value).and_then(|v| serde_json::to_string_pretty(&v))
+    } else {
+        serde_json::to_string(value)
+    };
+    match json {
+        Ok(json) => writeln!(out, "{json}")?,
+        Err(e) => eprintln!("failed to serialize JSON: {e}"),
+    }
+    Ok(())
+}
+
+/// Print details for a port. Returns `false` when nothing listens on it.
+fn cmd_port(
+    entries: &[PortEntry],
+    docker: &DockerSnapshot,
+    port: u16,
+    opts: &TableOptions,
+    detail_opts: &DetailOptions,
+    out: &mut dyn Write,
+) -> bool {
+    let Some(output) = port_output(entries, docker, port, detail_opts) else {
+        render(Output::NotFound(port), opts, out);
+        return false;
+    };
+    render(output.into(), opts, out);
+    true
+}
+
+/// What `porty port` found on a port
+#[derive(Serialize)]
+#[serde(untagged)]
+enum PortOutput {
+    Detail(Box<DetailedPortInfo>),
+    /// The details couldn't be gathered
+    Listing(Vec<PortEntry>),
+}
+
+impl From<PortOutput> for Output {
+    fn from(output: PortOutput) -> Self {
+        match output {
+            PortOutput::Detail(info) => Output::Detail(info),
+            PortOutput::Listing(found) => Output::Listing(found),
+        }
+    }
+}
+
+/// What `porty port` shows for `port`: the details of its first listener, or
+/// the plain listing when those can't be gathered. None when nothing listens.
+fn port_output(
+    entries: &[PortEntry],
+    docker: &DockerSnapshot,
+    port: u16,
+    detail_opts: &DetailOptions,
+) -> Option<PortOutput> {
+    let mut found: Vec<_> = entries.iter().filter(|e| e.port == port).cloned().collect();
+    if found.is_empty() {
+        return None;
+    }
+    apply_notes(&mut found);
+
+    // Get detailed info for the first matching entry
+    if let Some(entry) = found.first()
+        && let Some(pid) = entry.pid
+        && let Ok(mut detailed) = get_detailed_port_info(port, pid, entry.kind, docker, detail_opts)
+    {
+        detailed.note = entry.note.clone();
+        return Some(PortOutput::Detail(Box::new(detailed)));
+    }
+    // Fallback to the plain listing
+    Some(PortOutput::Listing(found))
+}
+
+/// `porty port` with several ports: each one's card, separated by dividers, a
+/// JSON array with an element per port (null where nothing listens), or a single
+/// CSV/TSV table. Succeeds only if every port has a listener.
+fn cmd_ports(
+    entries: &[PortEntry],
+    docker: &DockerSnapshot,
+    ports: &[u16],
+    opts: &TableOptions,
+    detail_opts: &DetailOptions,
+    out: &mut dyn Write,
+) -> bool {
+    let outputs: Vec<Option<PortOutput>> =
+        ports.iter().map(|&port| port_output(entries, docker, port, detail_opts)).collect();
+    let all_found = outputs.iter().all(Option::is_some);
+
+    let result = match opts.format {
+        OutputFormat::Json => print_json(&outputs, opts.pretty, out),
+        OutputFormat::Csv => print_ports_csv(&outputs, out),
+        OutputFormat::Tsv => print_ports_tsv(&outputs, out),
+        OutputFormat::Prometheus => print_ports_prometheus(ports, &outputs, out),
+        _ => {
+            for (i, (&port, output)) in ports.iter().zip(outputs).enumerate() {
+                if i > 0 && opts.format == OutputFormat::Table {
+                    let _ = writeln!(out, "\n{}\n", "─".repeat(71));
+                }
+                render(output.map_or(Output::NotFound(port), Output::from), opts, out);
+            }
+            return all_found;
+        }
+    };
+    if let Err(e) = result.and_then(|_| out.flush())
+        && e.kind() != std::io::ErrorKind::BrokenPipe
+    {
+        eprintln!("failed to write output: {e}");
+    }
+    all_found
+}
+
+/// Metrics for several ports, declaring each metric family once: the detailed
+/// metrics of ports with details, and `porty_port_listening` for the rest
+fn print_ports_prometheus(ports: &[u16], outputs: &[Option<PortOutput>], out: &mut dyn Write) -> std::io::Result<()> {
+    let mut details = Vec::new();
+    let mut listed = Vec::new();
+    for output in outputs.iter().flatten() {
+        match output {
+            PortOutput::Detail(info) => details.push(&**info),
+            PortOutput::Listing(found) => listed.extend(found),
+        }
+    }
+    let missing: Vec<u16> = ports
+        .iter()
+        .zip(outputs)
+        .filter(|(_, output)| output.is_none())
+        .map(|(&port, _)| port)
+        .collect();
+
+    if !details.is_empty() {
+        print_detailed_prometheus(&details, out)?;
+    }
+    if listed.is_empty() && missing.is_empty() {
+        return Ok(());
+    }
+    print_listening_header(out)?;
+    for e in listed {
+        print_listening_sample(e, out)?;
+    }
+    for port in missing {
+        writeln!(out, "porty_port_listening{{port=\"{port}\"}} 0")?;
+    }
+    Ok(())
+}
+
+/// One CSV table for several ports; listing rows leave the detail columns empty
+fn print_ports_csv(outputs: &[Option<PortOutput>], out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, "{DETAILED_CSV_HEADER}")?;
+    for output in outputs.iter().flatten() {
+        match output {
+            PortOutput::Detail(info) => print_detailed_csv_row(info, out)?,
+            PortOutput::Listing(found) => {
+                for e in found {
+                    let mut fields = vec![
+                        e.port.to_string(),
+                        e.pid.map(|p| p.to_string()).unwrap_or_default(),
+                        csv_field(e.process.as_deref().unwrap_or_default()),
+                        format_kind_id(e.kind).to_string(),
+                    ];
+                    fields.resize(DETAILED_CSV_HEADER.split(',').count(), String::new());
+                    writeln!(out, "{}", fields.join(","))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_ports_tsv(outputs: &[Option<PortOutput>], out: &mut dyn Write) -> std::io::Result<()> {
+    print_tsv_header(out)?;
+    for output in outputs.iter().flatten() {
+        match output {
+            PortOutput::Detail(info) => {
+                print_tsv_line(info.port, Some(&info.process_name), Some(info.pid), info.kind, info.exec_path.as_deref(), out)?
+            }
+            PortOutput::Listing(found) => {
+                for e in found {
+                    print_tsv_line(e.port, e.process.as_deref(), e.pid, e.kind, e.exec_path.as_deref(), out)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Gather the details of two ports and show them side by side
+fn cmd_compare(
+    entries: &[PortEntry],
+    docker: &DockerSnapshot,
+    (first, second): (u16, u16),
+    opts: &TableOptions,
+    detail_opts: &DetailOptions,
+    out: &mut dyn Write,
+) -> bool {
+    let details = |port: u16| -> Result<DetailedPortInfo> {
+        let entry = entries
+            .iter()
+            .find(|e| e.port == port && e.pid.is_some())
+            .ok_or_else(|| anyhow::anyhow!("No listener found on port {port}"))?;
+        let mut info = get_detailed_port_info(port, entry.pid.unwrap_or_default(), entry.kind, docker, detail_opts)?;
+        info.note = read_notes().ok().and_then(|mut notes| notes.remove(&port));
+        Ok(info)
+    };
+
+    match (details(first), details(second)) {
+        (Ok(a), Ok(b)) => {
+            render(Output::Compare(Box::new([a, b])), opts, out);
+            true
+        }
+        (a, b) => {
+            for e in [a.err(), b.err()].into_iter().flatten() {
+                eprintln!("{e:#}");
+            }
+            false
+        }
+    }
+}
+
+/// Write a Markdown report for sharing: a table of every port, then the detail
+/// card of each dev server and database
+fn cmd_export(
+    entries: &[PortEntry],
+    docker: &DockerSnapshot,
+    path: &Path,
+    opts: &TableOptions,
+    detail_opts: &DetailOptions,
+) -> Result<()> {
+    let mut out = open_output(path)?;
+    let host = hostname().unwrap_or_else(|| "unknown".to_string());
+    writeln!(out, "# Porty report")?;
+    writeln!(out)?;
+    writeln!(out, "Generated {} on `{}`.", Local::now().format("%Y-%m-%d %H:%M:%S %:z"), host)?;
+    writeln!(out)?;
+    writeln!(out, "## Listening ports")?;
+    writeln!(out)?;
+    if entries.is_empty() {
+        writeln!(out, "No ports found.")?;
+    } else {
+        writeln!(out, "| Port | Process | Category | PID | Address | Exec path |")?;
+        writeln!(out, "|-----:|---------|----------|----:|---------|-----------|")?;
+        for e in entries {
+            let cells = [
+                e.port.to_string(),
+                e.process.clone().unwrap_or("-".into()),
+                format_kind(e.kind).to_string(),
+                e.pid.map_or("-".into(), |pid| pid.to_string()),
+                join_addresses(&e.addresses),
+                e.exec_path.clone().unwrap_or("-".into()),
+            ];
+            let cells: Vec<String> = cells.iter().map(|c| markdown_cell(c)).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+
+    for e in entries.iter().filter(|e| matches!(e.kind, Kind::Dev | Kind::Database)) {
+        let Some(pid) = e.pid else { continue };
+        writeln!(out)?;
+        writeln!(out, "## Port {}: {}", e.port, markdown_cell(e.process.as_deref().unwrap_or("-")))?;
+        writeln!(out)?;
+        match get_detailed_port_info(e.port, pid, e.kind, docker, detail_opts) {
+            Ok(mut info) => {
+                info.note = e.note.clone();
+                // The report gets shared, so keep credentials out of it
+                redact_env(&mut info.env_vars);
+                writeln!(out, "```text")?;
+                print_detailed_port_info(&info, false, &opts.thresholds, false, &mut out)?;
+                writeln!(out, "```")?;
+            }
+            Err(err) => writeln!(out, "_Details unavailable: {err}_")?,
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Name fragments of environment variables that usually hold credentials;
+/// URLs count since they often embed a password (e.g. DATABASE_URL)
+const SECRET_ENV_MARKERS: [&str; 6] = ["KEY", "TOKEN", "SECRET", "PASSWORD", "PASSWD", "URL"];
+
+/// Hide the values of environment variables that look like credentials
+fn redact_env(env_vars: &mut [(String, String)]) {
+    for (key, value) in env_vars.iter_mut() {
+        let name = key.to_ascii_uppercase();
+        if SECRET_ENV_MARKERS.iter().any(|marker| name.contains(marker)) {
+            *value = "<redacted>".to_string();
+        }
+    }
+}
+
+/// Table cell text with the characters that would break a Markdown row escaped
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if rc != 0 {
+        return None;
+    }
+    let name = std::ffi::CStr::from_bytes_until_nul(&buf).ok()?;
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+/// The key fields of two ports in aligned columns; differing rows are marked
+fn print_compare(pair: &[DetailedPortInfo; 2], opts: &TableOptions, out: &mut dyn Write) -> std::io::Result<()> {
+    let fields: [(&str, DetailField<String>); 12] = [
+        ("Process", |i| i.process_name.clone()),
+        ("PID", |i| i.pid.to_string()),
+        ("Category", |i| format_kind(i.kind).to_string()),
+        ("User", |i| i.user_name.clone()),
+        ("Uptime", |i| i.uptime.clone()),
+        ("Memory (RSS)", |i| format!("{} MB", format_mb(i.memory_rss))),
+        ("CPU", |i| format!("{}%", format_float(i.cpu_usage, 1))),
+        ("Threads", |i| i.thread_count.to_string()),
+        ("Connections", |i| i.active_connections.to_string()),
+        ("Exec Path", |i| i.exec_path.clone().unwrap_or("-".into())),
+        ("Directory", |i| i.working_dir.clone().unwrap_or("-".into())),
+        ("Command", |i| shorten(&sanitize(&i.command), MAX_COMMAND_CHARS).into_owned()),
+    ];
+
+    let mut table = new_table(opts);
+    table.set_header(vec![
+        String::new(),
+        format!("PORT {}", pair[0].port),
+        format!("PORT {}", pair[1].port),
+    ]);
+    let mut any_differ = false;
+    for (label, field) in fields {
+        let (a, b) = (field(&pair[0]), field(&pair[1]));
+        let differs = a != b;
+        any_differ |= differs;
+        let label = if differs { format!("{label} *") } else { label.to_string() };
+        let cell = |value: String| {
+            if differs && opts.colors { Cell::new(value).fg(Color::Yellow) } else { Cell::new(value) }
+        };
+        table.add_row(vec![Cell::new(label), cell(a), cell(b)]);
+    }
+
+    writeln!(out, "{table}")?;
+    if any_differ {
+        writeln!(out, "* differs between the two ports")?;
+    }
+    Ok(())
+}
+
+/// Where `port --watch --watch-file` records its snapshots
+struct WatchLog {
+    path: PathBuf,
+    max_size: u64,
+}
+
+/// One line of the watch file
+#[derive(Serialize)]
+struct WatchSnapshot<'a> {
+    time: String,
+    ports: &'a [PortEntry],
+}
+
+impl WatchLog {
+    /// Append a timestamped snapshot. Once the file has grown past `max_size` it
+    /// is moved to `<path>.1`, replacing the previous one, and started afresh.
+    fn append(&self, entries: &[PortEntry]) -> Result<()> {
+        if std::fs::metadata(&self.path).is_ok_and(|m| m.len() >= self.max_size) {
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            std::fs::rename(&self.path, &rotated)
+                .with_context(|| format!("failed to rotate {}", self.path.display()))?;
+        }
+        let snapshot = WatchSnapshot { time: Local::now().to_rfc3339(), ports: entries };
+        let line = serde_json::to_string(&snapshot)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open {}", self.path.display()))?;
+        writeln!(file, "{line}").with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+/// When `port --watch` refreshes
+#[derive(Debug, Clone, Copy)]
+struct WatchSchedule {
+    interval: Duration,
+    /// Upper bound of the random delay added to each wait
+    jitter: Duration,
+}
+
+impl WatchSchedule {
+    /// How long to wait after a poll that took `took`, and whether that backs
+    /// off. Slow polls (discovery on a busy machine) stretch the wait to twice
+    /// their duration, so watch never spends more than a third of its time
+    /// running lsof/ps/docker.
+    fn next_wait(&self, took: Duration) -> (Duration, bool) {
+        let backing_off = took * 2 > self.interval;
+        let wait = self.interval.max(took * 2);
+        if self.jitter.is_zero() {
+            return (wait, backing_off);
+        }
+        // Random enough to keep several watchers from polling in lockstep
+        use std::hash::{BuildHasher, Hasher};
+        let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+        (wait + self.jitter.mul_f64((random % 1000) as f64 / 1000.0), backing_off)
+    }
+}
+
+/// `porty port --watch`: which port, how often, and where to log
+struct PortWatch {
+    port: u16,
+    schedule: WatchSchedule,
+    log: Option<WatchLog>,
+    /// Keep porty's own process in each rescan (--include-self)
+    include_self: bool,
+    /// Keep other users' sockets in each rescan (--all-users)
+    all_users: bool,
+}
+
+fn watch_port(
+    config: &Config,
+    entries: Vec<PortEntry>,
+    docker: DockerSnapshot,
+    watch: &PortWatch,
+    opts: &TableOptions,
+    detail_opts: &DetailOptions,
+) {
+    let PortWatch { port, schedule, ref log, include_self, all_users } = *watch;
+    let mut entries = entries;
+    let mut docker = docker;
+    // The first scan happened before we got here
+    let mut discovery_took = Duration::ZERO;
+    loop {
+        // Build the card first so the screen isn't blank while a slow poll runs
+        let started = std::time::Instant::now();
+        let mut card = Vec::new();
+        cmd_port(&entries, &docker, port, opts, detail_opts, &mut card);
+        let took = discovery_took + started.elapsed();
+        let (wait, backing_off) = schedule.next_wait(took);
+
+        // Clear the screen and move the cursor home before redrawing the card
+        print!("\x1b[2J\x1b[H");
+        println!("Every {}s: porty port {} (Ctrl-C to exit)", schedule.interval.as_secs(), port);
+        let _ = std::io::stdout().write_all(&card);
+        let backoff = if backing_off { ", backing off" } else { "" };
+        println!(
+            "Poll took {}s; next refresh in {}s{}",
+            format_float(took.as_secs_f64(), 1),
+            format_float(wait.as_secs_f64(), 1),
+            backoff
+        );
+        if let Some(log) = log
+            && let Err(e) = log.append(&entries)
+        {
+            eprintln!("watch file error: {e:#}");
+        }
+
+        thread::sleep(wait);
+        let started = std::time::Instant::now();
+        restart_deadline();
+        docker = DockerSnapshot::new(docker.enabled);
+        entries = discover_ports(config, &docker).unwrap_or_else(|e| {
+            eprintln!("discovery error: {e}");
+            vec![]
+        });
+        // Narrowed like the first scan was in main
+        entries = if include_self { entries } else { filter_self(entries) };
+        entries = if all_users { entries } else { filter_own_user(entries) };
+        discovery_took = started.elapsed();
+    }
+}
+
+/// What `porty scan` probes
+struct ScanOptions {
+    range: (u16, u16),
+    open_only: bool,
+    timeout: Duration,
+    /// Address to connect to; loopback when unset
+    addr: Option<std::net::IpAddr>,
+}
+
+/// Connect to every port in the range and list the ones that answer, along with
+/// their owner when discovery knows it
+fn cmd_scan(
+    entries: &[PortEntry],
+    config: &Config,
+    scan: &ScanOptions,
+    opts: &TableOptions,
+    out: &mut dyn Write,
+) {
+    let (start, end) = scan.range;
+    let mut results = Vec::new();
+    for (port, health) in scan_ports(start, end, scan.timeout, scan.addr) {
+        if scan.open_only && health != Health::Ok {
+            continue;
+        }
+        let known: Vec<_> = entries
+            .iter()
+            .filter(|e| e.port == port)
+            .filter(|e| scan.addr.is_none_or(|addr| binds_overlap(&e.addresses, addr)))
+            .collect();
+        if known.is_empty() {
+            let ip = scan.addr.unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+            let family = if ip.is_ipv6() { IpFamily::V6 } else { IpFamily::V4 };
+            let addr = std::net::SocketAddr::new(ip, port).to_string();
+            results.push(PortEntry {
+                port,
+                pid: None,
+                process: None,
+                exec_path: None,
+                kind: classify(port, None, None, None, &addr, config),
+                addresses: vec![(family, addr)],
+                protocol: Protocol::Tcp,
+                uid: None,
+                project: false,
+                health: Some(health),
+                connections: None,
+                service: None,
+                note: None,
+                brew_service: None,
+                cwd: None,
+                memory_kb: None,
+                cpu_percent: None,
+                other_ports: Vec::new(),
+            });
+        } else {
+            for entry in known {
+                results.push(PortEntry { health: Some(health), ..entry.clone() });
+            }
+        }
+    }
+    apply_notes(&mut results);
+    render(Output::Listing(results), opts, out);
+}
+
+/// Probe `start..=end` on `addr`, or on loopback (IPv4, then IPv6), with a fixed
+/// pool of workers
+fn scan_ports(start: u16, end: u16, timeout: Duration, addr: Option<std::net::IpAddr>) -> Vec<(u16, Health)> {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const SCAN_WORKERS: usize = 64;
+
+    let targets = match addr {
+        Some(ip) => vec![ip],
+        None => vec![IpAddr::V4(Ipv4Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::LOCALHOST)],
+    };
+
+    let next = AtomicU32::new(start as u32);
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..SCAN_WORKERS {
+            scope.spawn(|| {
+                loop {
+                    let port = next.fetch_add(1, Ordering::Relaxed);
+                    if port > end as u32 {
+                        break;
+                    }
+                    let port = port as u16;
+                    let mut health = Health::Refused;
+                    for ip in &targets {
+                        health = match TcpStream::connect_timeout(&SocketAddr::new(*ip, port), timeout) {
+                            Ok(_) => Health::Ok,
+                            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => Health::Timeout,
+                            Err(_) => Health::Refused,
+                        };
+                        if health == Health::Ok {
+                            break;
+                        }
+                    }
+                    results.lock().unwrap().push((port, health));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(port, _)| *port);
+    results
+}
+
+fn cmd_free(entries: &[PortEntry], port: u16, addr: Option<std::net::IpAddr>) {
+    let on_port: Vec<_> = entries.iter().filter(|e| e.port == port).collect();
+    let (found, elsewhere): (Vec<_>, Vec<_>) = on_port
+        .into_iter()
+        .partition(|e| addr.is_none_or(|addr| binds_overlap(&e.addresses, addr)));
+    let scope = addr.map(|addr| format!(" on {addr}")).unwrap_or_default();
+
+    if found.is_empty() {
+        match addr {
+            Some(addr) => match std::net::TcpListener::bind((addr, port)) {
+                Ok(_) => println!("Port {port} is free{scope}"),
+                Err(e) => println!("Port {port} can't be bound{scope}: {e}"),
+            },
+            None => println!("No TCP listener found on port {port}"),
+        }
+        for entry in elsewhere {
+            if let (Some(pid), Some(process)) = (entry.pid, &entry.process) {
+                println!("  In use on {} by {} (PID {})", join_addresses(&entry.addresses), process, pid);
+            }
+        }
+        let waiting = count_time_wait(port);
+        if waiting > 0 {
+            println!(
+                "Port {port} appears free but has {waiting} socket{} in TIME_WAIT; it may not be immediately bindable",
+                if waiting == 1 { "" } else { "s" }
+            );
+        }
+    } else {
+        println!("Port {port} is in use{scope}:");
+        for entry in found {
+            if let (Some(pid), Some(process)) = (entry.pid, &entry.process) {
+                println!("  {} (PID {})", process, pid);
+                println!("  Hint: kill {} or use 'porty kill {}'", pid, port);
+            }
+        }
+    }
+}
+
+/// Whether a listener bound to `addresses` keeps `addr` from binding the same
+/// port: the same IP, or a wildcard on either side of the same family. An IPv6
+/// wildcard is usually dual-stack, so it covers IPv4 too.
+fn binds_overlap(addresses: &[(IpFamily, String)], addr: std::net::IpAddr) -> bool {
+    let family = if addr.is_ipv6() { IpFamily::V6 } else { IpFamily::V4 };
+    addresses.iter().any(|(bound_family, bound)| {
+        let host = address_host(bound);
+        let wildcard = matches!(host, "*" | "0.0.0.0" | "::");
+        let same_family = *bound_family == family;
+        if wildcard {
+            same_family || *bound_family == IpFamily::V6
+        } else if addr.is_unspecified() {
+            same_family
+        } else {
+            host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip == addr)
+        }
+    })
+}
+
+/// Closed connections still in TIME_WAIT on a local port. They have no owning
+/// process, so lsof doesn't see them, but they can make bind() fail for a while.
+fn count_time_wait(port: u16) -> usize {
+    use std::process::Command;
+
+    let Ok(output) = Command::new("netstat").arg("-an").output_within() else {
+        return 0;
+    };
+
+    // The local address is two columns before the state on every platform:
+    //   tcp4  0  0  127.0.0.1.3000  127.0.0.1.54321  TIME_WAIT     (macOS)
+    //   TCP   127.0.0.1:3000        127.0.0.1:54321  TIME_WAIT  0  (Windows)
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| line.trim_start().to_ascii_lowercase().starts_with("tcp"))
+        .filter(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some(state) = fields.iter().position(|f| *f == "TIME_WAIT") else {
+                return false;
+            };
+            state >= 2
+                && fields[state - 2]
+                    .rsplit(['.', ':'])
+                    .next()
+                    .and_then(|p| p.parse::<u16>().ok())
+                    == Some(port)
+        })
+        .count()
+}
+
+/// Puts the terminal in raw mode on the alternate screen, and restores it on drop
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> std::io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        crossterm::execute!(
+            std::io::stdout(),
+            crossterm::terminal::EnterAlternateScreen,
+            crossterm::cursor::Hide
+        )?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(
+            std::io::stdout(),
+            crossterm::cursor::Show,
+            crossterm::terminal::LeaveAlternateScreen
+        );
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TuiKey {
+    Up,
+    Down,
+    Enter,
+    Esc,
+    Backspace,
+    Char(char),
+    /// Ctrl-C, which quits even while typing a filter
+    Quit,
+}
+
+/// Wait up to `timeout` for a key press
+fn read_key(timeout: Duration) -> std::io::Result<Option<TuiKey>> {
+    use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+
+    if !crossterm::event::poll(timeout)? {
+        return Ok(None);
+    }
+    let Event::Key(key) = crossterm::event::read()? else {
+        return Ok(None);
+    };
+    // Windows reports releases too
+    if key.kind == KeyEventKind::Release {
+        return Ok(None);
+    }
+    Ok(Some(match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => TuiKey::Quit,
+        KeyCode::Up => TuiKey::Up,
+        KeyCode::Down => TuiKey::Down,
+        KeyCode::Enter => TuiKey::Enter,
+        KeyCode::Esc => TuiKey::Esc,
+        KeyCode::Backspace => TuiKey::Backspace,
+        KeyCode::Char(c) => TuiKey::Char(c),
+        _ => return Ok(None),
+    }))
+}
+
+struct TuiState {
+    entries: Vec<PortEntry>,
+    selected: usize,
+    filter: String,
+    editing_filter: bool,
+    /// Detailed card of a port, shown after `d`
+    detail: Option<(u16, Vec<String>)>,
+    /// Entry waiting for a y/n kill confirmation
+    confirm_kill: Option<PortEntry>,
+    status: String,
+    docker_enabled: bool,
+}
+
+impl TuiState {
+    fn visible(&self) -> Vec<PortEntry> {
+        if self.filter.is_empty() {
+            self.entries.clone()
+        } else {
+            filter_find(&self.entries, &self.filter)
+        }
+    }
+
+    fn selected_entry(&self) -> Option<PortEntry> {
+        self.visible().into_iter().nth(self.selected)
+    }
+
+    /// Swap in a fresh scan, keeping the same port selected when it's still there
+    fn set_entries(&mut self, entries: Vec<PortEntry>) {
+        let selected_port = self.selected_entry().map(|e| e.port);
+        self.entries = entries;
+        let visible = self.visible();
+        self.selected = selected_port
+            .and_then(|port| visible.iter().position(|e| e.port == port))
+            .unwrap_or(self.selected)
+            .min(visible.len().saturating_sub(1));
+    }
+
+    /// Apply a key press. Returns `false` to quit.
+    fn handle(&mut self, key: TuiKey) -> bool {
+        if key == TuiKey::Quit {
+            return false;
+        }
+        if let Some(entry) = self.confirm_kill.take() {
+            let pid = entry.pid.unwrap_or_default();
+            let process = entry.process.unwrap_or_default();
+            self.status = if key == TuiKey::Char('y') {
+                match kill_pid(pid, None) {
+                    Ok(()) => format!("Killed {} (PID {})", process, pid),
+                    Err(e) => format!("Failed to kill {} (PID {}): {}", process, pid, e),
+                }
+            } else {
+                "Kill cancelled".to_string()
+            };
+            return true;
+        }
+
+        if self.editing_filter {
+            match key {
+                TuiKey::Enter => self.editing_filter = false,
+                TuiKey::Esc => {
+                    self.editing_filter = false;
+                    self.filter.clear();
+                }
+                TuiKey::Backspace => {
+                    self.filter.pop();
+                }
+                TuiKey::Char(c) if !c.is_control() => self.filter.push(c),
+                _ => {}
+            }
+            self.selected = 0;
+            return true;
+        }
+
+        let count = self.visible().len();
+        match key {
+            TuiKey::Char('q') => return false,
+            TuiKey::Esc => self.detail = None,
+            TuiKey::Up => self.selected = self.selected.saturating_sub(1),
+            TuiKey::Down => self.selected = (self.selected + 1).min(count.saturating_sub(1)),
+            TuiKey::Char('/') => {
+                self.editing_filter = true;
+                self.detail = None;
+            }
+            TuiKey::Char('d') | TuiKey::Enter => self.toggle_detail(),
+            TuiKey::Char('k') => match self.selected_entry() {
+                Some(entry) if entry.pid.is_some() => {
+                    self.status.clear();
+                    self.confirm_kill = Some(entry);
+                }
+                Some(_) => self.status = "No process to kill on this port".to_string(),
+                None => {}
+            },
+            _ => {}
+        }
+        true
+    }
+
+    fn toggle_detail(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if self.detail.as_ref().is_some_and(|(port, _)| *port == entry.port) {
+            self.detail = None;
+            return;
+        }
+        let Some(pid) = entry.pid else {
+            self.status = "No process on this port".to_string();
+            return;
+        };
+
+        let docker = DockerSnapshot::new(self.docker_enabled);
+        let lines = match get_detailed_port_info(entry.port, pid, entry.kind, &docker, &DetailOptions::default()) {
+            Ok(mut info) => {
+                info.note = read_notes().ok().and_then(|mut notes| notes.remove(&entry.port));
+                let mut card = Vec::new();
+                let _ = print_detailed_port_info(&info, false, &Thresholds::default(), false, &mut card);
+                String::from_utf8_lossy(&card).lines().map(str::to_string).collect()
+            }
+            Err(e) => vec![format!("No details for port {}: {}", entry.port, e)],
+        };
+        self.detail = Some((entry.port, lines));
+    }
+
+    fn draw(&self, out: &mut impl Write) -> std::io::Result<()> {
+        use crossterm::cursor::MoveTo;
+        use crossterm::style::{Attribute, SetAttribute};
+        use crossterm::terminal::{Clear, ClearType};
+
+        let (cols, rows) = crossterm::terminal::size()?;
+        let (cols, rows) = (cols as usize, rows as usize);
+        let visible = self.visible();
+        // The card goes in a side panel when there's room, full screen otherwise
+        let side_panel = cols >= 100;
+        let list_width = if self.detail.is_some() && side_panel { cols / 2 } else { cols };
+        let body_rows = rows.saturating_sub(3);
+
+        crossterm::queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        let mut title = format!("porty: {} port{}", visible.len(), if visible.len() == 1 { "" } else { "s" });
+        if !self.filter.is_empty() {
+            title.push_str(&format!(" matching '{}'", self.filter));
+        }
+        crossterm::queue!(out, SetAttribute(Attribute::Bold))?;
+        write!(out, "{}", fit(&title, cols))?;
+        crossterm::queue!(out, SetAttribute(Attribute::Reset))?;
+
+        if self.detail.is_none() || side_panel {
+            crossterm::queue!(out, MoveTo(0, 1), SetAttribute(Attribute::Underlined))?;
+            write!(out, "{}", fit(&format!("{:<6} {:<24} {:<11} {:>7}", "PORT", "PROCESS", "CATEGORY", "PID"), list_width))?;
+            crossterm::queue!(out, SetAttribute(Attribute::Reset))?;
+
+            // Scroll so the selection stays on screen
+            let offset = (self.selected + 1).saturating_sub(body_rows);
+            for (row, (i, e)) in visible.iter().enumerate().skip(offset).take(body_rows).enumerate() {
+                let line = format!(
+                    "{:<6} {:<24} {:<11} {:>7}",
+                    e.port,
+                    fit(e.process.as_deref().unwrap_or("-"), 24),
+                    format_kind(e.kind),
+                    e.pid.map(|p| p.to_string()).unwrap_or("-".into()),
+                );
+                crossterm::queue!(out, MoveTo(0, (row + 2) as u16))?;
+                if i == self.selected {
+                    crossterm::queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                write!(out, "{:<1$}", fit(&line, list_width), list_width)?;
+                crossterm::queue!(out, SetAttribute(Attribute::Reset))?;
+            }
+        }
+
+        if let Some((_, ref lines)) = self.detail {
+            let (x, width) = if side_panel { (list_width + 1, cols - list_width - 1) } else { (0, cols) };
+            for (row, line) in lines.iter().take(rows.saturating_sub(2)).enumerate() {
+                crossterm::queue!(out, MoveTo(x as u16, (row + 1) as u16))?;
+                write!(out, "{}", fit(line, width))?;
+            }
+        }
 
-fn filter_dev(entries: &[PortEntry]) -> Vec<PortEntry> {
-    entries.iter()
-        .filter(|e| matches!(e.kind, Kind::Dev))
-        .cloned()
-        .collect()
+        let footer = if let Some(ref entry) = self.confirm_kill {
+            format!(
+                "Kill {} (PID {})? y/n",
+                entry.process.as_deref().unwrap_or("-"),
+                entry.pid.unwrap_or_default()
+            )
+        } else if self.editing_filter {
+            format!("/{}", self.filter)
+        } else if !self.status.is_empty() {
+            self.status.clone()
+        } else {
+            "up/down select  d details  k kill  / filter  q quit".to_string()
+        };
+        crossterm::queue!(out, MoveTo(0, rows.saturating_sub(1) as u16), SetAttribute(Attribute::Reverse))?;
+        write!(out, "{:<1$}", fit(&footer, cols), cols)?;
+        crossterm::queue!(out, SetAttribute(Attribute::Reset))?;
+        out.flush()
+    }
 }
 
-fn filter_prod(entries: &[PortEntry]) -> Vec<PortEntry> {
-    entries.iter()
-        .filter(|e| matches!(e.kind, Kind::Dev | Kind::Container))
-        .cloned()
-        .collect()
+/// Cut `text` to at most `width` characters
+fn fit(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
 }
 
-fn classify(port: u16, process: Option<&str>) -> Kind {
-    // Process-based rules take priority (more accurate)
-    if let Some(p) = process {
-        let p = p.to_lowercase();
+fn cmd_tui(
+    config: &Config,
+    entries: Vec<PortEntry>,
+    docker_enabled: bool,
+    all_users: bool,
+    include_self: bool,
+    interval: Duration,
+) -> Result<()> {
+    use std::io::IsTerminal;
 
-        // macOS system processes (check first to avoid misclassification)
-        if p.contains("launchd") || p.contains("mdnsresponder") || p.contains("cups")
-            || p.contains("controlcenter") || p.contains("airplay") {
-            return Kind::System;
-        }
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(anyhow::anyhow!("porty tui needs an interactive terminal"));
+    }
 
-        // Dev servers
-        if p.contains("node") || p.contains("vite") || p.contains("next")
-            || p.contains("python") || p.contains("ruby") || p.contains("rails")
-            || p.contains("django") || p.contains("flask") || p.contains("phoenix")
-            || p.contains("webpack") || p.contains("npm") || p.contains("yarn")
-            || p.contains("puma") || p.contains("unicorn") {
-            return Kind::Dev;
+    // Rescan in the background so key presses stay responsive
+    let (tx, rx) = std::sync::mpsc::channel();
+    let poll_config = config.clone();
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        restart_deadline();
+        let docker = DockerSnapshot::new(docker_enabled);
+        let Ok(entries) = discover_ports(&poll_config, &docker) else {
+            continue;
+        };
+        let entries = if include_self { entries } else { filter_self(entries) };
+        let entries = if all_users { entries } else { filter_own_user(entries) };
+        if tx.send(entries).is_err() {
+            break;
         }
+    });
 
-        // Databases
-        if p.contains("postgres") || p.contains("mysql") || p.contains("redis")
-            || p.contains("mongod") || p.contains("mariadb") || p.contains("couchdb") {
-            return Kind::Database;
-        }
+    let mut state = TuiState {
+        entries,
+        selected: 0,
+        filter: String::new(),
+        editing_filter: false,
+        detail: None,
+        confirm_kill: None,
+        status: String::new(),
+        docker_enabled,
+    };
 
-        // Containers
-        if p.contains("docker") || p.contains("containerd") || p.contains("colima")
-            || p.contains("podman") {
-            return Kind::Container;
+    let _guard = TerminalGuard::enter()?;
+    let mut stdout = std::io::stdout();
+    let mut size = crossterm::terminal::size()?;
+    state.draw(&mut stdout)?;
+    loop {
+        let mut dirty = false;
+        while let Ok(entries) = rx.try_recv() {
+            state.set_entries(entries);
+            dirty = true;
+        }
+        if let Some(key) = read_key(Duration::from_millis(200))? {
+            if !state.handle(key) {
+                return Ok(());
+            }
+            dirty = true;
+        }
+        let new_size = crossterm::terminal::size()?;
+        if dirty || new_size != size {
+            size = new_size;
+            state.draw(&mut stdout)?;
         }
     }
+}
 
-    // Port-based rules (fallback when process is unknown or doesn't match)
-    match port {
-        3000 | 5173 | 8080 | 8000 | 4200 | 3001 | 5000 | 9000 => Kind::Dev,
-        5432 | 3306 | 6379 | 27017 | 1433 | 5984 => Kind::Database,
-        2375 | 2376 => Kind::Container,
-        631 => Kind::System,
-        _ => Kind::Unknown,
-    }
+/// One line of the `porty doctor` report
+struct DoctorCheck {
+    name: String,
+    passed: bool,
+    /// Listing ports doesn't work without it
+    required: bool,
+    detail: String,
+    hint: &'static str,
 }
 
-use nix::sys::signal::{kill, Signal};
-use nix::unistd::Pid;
-use std::{thread, time::Duration};
+/// Print a PASS/FAIL report of porty's environment. False when a required check failed.
+fn cmd_doctor(config: Option<&Path>, colors: bool) -> bool {
+    #[cfg(unix)]
+    let tools: [(&str, bool, &[&str], &'static str); 2] = [
+        ("lsof", true, &["-v"], "lsof ships with macOS; make sure /usr/sbin is on your PATH"),
+        ("ps", true, &[], "ps ships with the OS; make sure /bin is on your PATH"),
+    ];
+    #[cfg(not(unix))]
+    let tools: [(&str, bool, &[&str], &'static str); 3] = [
+        ("netstat", true, &[], "netstat ships with Windows; make sure System32 is on your PATH"),
+        ("tasklist", true, &[], "tasklist ships with Windows; make sure System32 is on your PATH"),
+        ("taskkill", false, &[], "only needed for `porty kill`"),
+    ];
 
-fn print_banner(colors: bool) {
-    const BANNER: &str = include_str!("../banner.txt");
+    let mut checks: Vec<DoctorCheck> = tools
+        .into_iter()
+        .map(|(name, required, version_args, hint)| tool_check(name, required, version_args, hint))
+        .collect();
+    checks.push(tool_check(
+        "docker",
+        false,
+        &["--version"],
+        "only needed for container names; install Docker or pass --no-docker",
+    ));
+    checks.push(own_process_check());
 
-    if colors {
-        // Rainbow colors for each line
-        let rainbow_colors = [
-            Color::Red,
-            Color::Yellow,
-            Color::Green,
-            Color::Cyan,
-            Color::Blue,
-            Color::Magenta,
-        ];
+    let config_path = config.map(Path::to_path_buf).or_else(default_config_path);
+    checks.push(match load_config(config) {
+        Ok(_) => DoctorCheck {
+            name: "config".into(),
+            passed: true,
+            required: true,
+            detail: match config_path {
+                Some(path) if path.exists() => path.display().to_string(),
+                _ => "no config file, using defaults".into(),
+            },
+            hint: "",
+        },
+        Err(e) => DoctorCheck {
+            name: "config".into(),
+            passed: false,
+            required: true,
+            detail: format!("{e:#}"),
+            hint: "fix the file, or point --config / PORTY_CONFIG at another one",
+        },
+    });
 
-        for (i, line) in BANNER.lines().enumerate() {
-            let color = rainbow_colors[i % rainbow_colors.len()];
-            let colored_line = match color {
-                Color::Red => format!("\x1b[31m{}\x1b[0m", line),
-                Color::Yellow => format!("\x1b[33m{}\x1b[0m", line),
-                Color::Green => format!("\x1b[32m{}\x1b[0m", line),
-                Color::Cyan => format!("\x1b[36m{}\x1b[0m", line),
-                Color::Blue => format!("\x1b[34m{}\x1b[0m", line),
-                Color::Magenta => format!("\x1b[35m{}\x1b[0m", line),
-                _ => line.to_string(),
-            };
-            println!("{}", colored_line);
+    let (green, red, reset) = if colors { ("\x1b[32m", "\x1b[31m", "\x1b[0m") } else { ("", "", "") };
+    println!("porty {} ({} {})", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH);
+    println!();
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for check in &checks {
+        let (status, color) = if check.passed { ("PASS", green) } else { ("FAIL", red) };
+        let optional = if !check.passed && !check.required { " (optional)" } else { "" };
+        println!("{color}{status}{reset}  {:<width$}  {}{}", check.name, check.detail, optional);
+        if !check.passed && !check.hint.is_empty() {
+            println!("      {:<width$}  hint: {}", "", check.hint);
         }
+    }
+
+    let failed = checks.iter().filter(|c| !c.passed).count();
+    println!();
+    if failed == 0 {
+        println!("All checks passed.");
     } else {
-        println!("{}", BANNER);
+        println!("{} check{} failed.", failed, if failed == 1 { "" } else { "s" });
     }
+    checks.iter().all(|c| c.passed || !c.required)
 }
 
-fn kill_pid(pid: u32) -> anyhow::Result<()> {
-    let pid = Pid::from_raw(pid as i32);
-    kill(pid, Signal::SIGTERM)?;
-    thread::sleep(Duration::from_millis(300));
+fn tool_check(name: &str, required: bool, version_args: &[&str], hint: &'static str) -> DoctorCheck {
+    let Some(path) = find_on_path(name) else {
+        return DoctorCheck { name: name.into(), passed: false, required, detail: "not found on PATH".into(), hint };
+    };
+    let mut detail = path.display().to_string();
+    if !version_args.is_empty()
+        && let Some(version) = tool_version(&path, version_args)
+    {
+        detail = format!("{detail} ({version})");
+    }
+    DoctorCheck { name: name.into(), passed: true, required, detail, hint }
+}
 
-    if kill(pid, None).is_ok() {
-        kill(pid, Signal::SIGKILL)?;
+/// The version line of a tool's output, e.g. "revision: 4.91" from `lsof -v`
+/// (which prints to stderr) or "Docker version 27.0.3, build 7d4bcd8"
+fn tool_version(path: &Path, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(path).args(args).output_within().ok()?;
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines
+        .iter()
+        .find(|l| l.starts_with("revision:"))
+        .or(lines.first())
+        .map(|l| l.to_string())
+}
+
+/// Whether porty can inspect a process at all, tried on itself
+#[cfg(unix)]
+fn own_process_check() -> DoctorCheck {
+    let pid = std::process::id();
+    let ps = get_command_line(pid).is_some();
+    let lsof = std::process::Command::new("lsof")
+        .args(["-p", &pid.to_string()])
+        .output_within()
+        .is_ok_and(|o| o.status.success() && !o.stdout.is_empty());
+    let detail = match (ps, lsof) {
+        (true, true) => format!("ps and lsof can read PID {pid}"),
+        (false, true) => format!("ps can't read PID {pid}"),
+        (true, false) => format!("lsof can't read PID {pid}"),
+        (false, false) => format!("neither ps nor lsof can read PID {pid}"),
+    };
+    DoctorCheck {
+        name: "own process".into(),
+        passed: ps && lsof,
+        required: true,
+        detail,
+        hint: "a sandbox or security tool may be blocking process inspection",
     }
-    Ok(())
 }
 
-fn main() {
-    let cli = Cli::parse();
+#[cfg(windows)]
+fn own_process_check() -> DoctorCheck {
+    let pid = std::process::id();
+    let passed = get_process_names_windows().contains_key(&pid);
+    DoctorCheck {
+        name: "own process".into(),
+        passed,
+        required: true,
+        detail: if passed { format!("tasklist can read PID {pid}") } else { format!("tasklist can't read PID {pid}") },
+        hint: "a sandbox or security tool may be blocking process inspection",
+    }
+}
 
-    let entries = discover_ports().unwrap_or_else(|e| {
-        eprintln!("discovery error: {e}");
-        vec![]
-    });
+/// Which entries `porty kill` should act on. All given criteria must match.
+struct KillTarget {
+    port: Option<u16>,
+    pid: Option<u32>,
+    kind: Option<Kind>,
+    name: Option<String>,
+    /// Other users' processes may be signalled too (--all-users)
+    all_users: bool,
+}
 
-    match cli.cmd {
-        None => {
-            print_banner(cli.colors);
-            let filtered = filter_default(&entries);
-            print_table(filtered, cli.verbose, cli.colors);
+impl KillTarget {
+    fn matches(&self, entry: &PortEntry) -> bool {
+        if self.port.is_some_and(|port| entry.port != port) {
+            return false;
         }
-        Some(Cmd::All) => {
-            print_banner(cli.colors);
-            print_table(entries, cli.verbose, cli.colors);
+        if self.pid.is_some_and(|pid| entry.pid != Some(pid)) {
+            return false;
         }
-        Some(Cmd::Dev) => {
-            print_banner(cli.colors);
-            let filtered = filter_dev(&entries);
-            print_table(filtered, cli.verbose, cli.colors);
+        if self.kind.is_some_and(|kind| entry.kind != kind) {
+            return false;
         }
-        Some(Cmd::Prod) => {
-            print_banner(cli.colors);
-            let filtered = filter_prod(&entries);
-            print_table(filtered, cli.verbose, cli.colors);
+        if let Some(ref name) = self.name {
+            let process = entry.process.as_deref().unwrap_or("").to_lowercase();
+            if !process.contains(&name.to_lowercase()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Human description, e.g. "port 3000" or "Dev Server processes named 'node'"
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(kind) = self.kind {
+            parts.push(format!("{} processes", format_kind(kind)));
         }
-        Some(Cmd::Port { port }) => {
-            print_banner(cli.colors);
-            cmd_port(&entries, port, cli.verbose, cli.colors);
+        if let Some(ref name) = self.name {
+            parts.push(format!("processes named '{}'", name));
         }
-        Some(Cmd::Free { port }) => {
-            cmd_free(&entries, port);
+        if let Some(port) = self.port {
+            parts.push(format!("port {}", port));
         }
-        Some(Cmd::Kill { port, force }) => {
-            cmd_kill(&entries, port, force);
+        if let Some(pid) = self.pid {
+            parts.push(format!("PID {}", pid));
         }
+        parts.join(" / ")
     }
-}
 
-fn cmd_port(entries: &[PortEntry], port: u16, verbose: bool, colors: bool) {
-    let found: Vec<_> = entries.iter().cloned().filter(|e| e.port == port).collect();
-    if found.is_empty() {
-        println!("No listener found on port {port}");
-    } else {
-        // Get detailed info for the first matching entry
-        if let Some(entry) = found.first() {
-            if let Some(pid) = entry.pid {
-                if let Ok(detailed) = get_detailed_port_info(port, pid, entry.kind) {
-                    print_detailed_port_info(&detailed, colors);
-                    return;
-                }
-            }
+    /// The command-line arguments selecting this target
+    fn args(&self) -> String {
+        let mut args = Vec::new();
+        if let Some(port) = self.port {
+            args.push(port.to_string());
+        }
+        if let Some(pid) = self.pid {
+            args.push(format!("--pid {}", pid));
         }
-        // Fallback to table view
-        print_table(found, verbose, colors);
+        if let Some(value) = self.kind.and_then(|kind| kind.to_possible_value()) {
+            args.push(format!("--kind {}", value.get_name()));
+        }
+        if let Some(ref name) = self.name {
+            args.push(format!("--name {}", name));
+        }
+        args.join(" ")
     }
 }
 
-fn cmd_free(entries: &[PortEntry], port: u16) {
-    let found: Vec<_> = entries.iter().filter(|e| e.port == port).collect();
-    if found.is_empty() {
-        println!("No TCP listener found on port {port}");
-    } else {
-        println!("Port {port} is in use:");
-        for entry in found {
-            if let (Some(pid), Some(process)) = (entry.pid, &entry.process) {
-                println!("  {} (PID {})", process, pid);
-                println!("  Hint: kill {} or use 'porty kill {}'", pid, port);
-            }
-        }
-    }
+/// `porty kill --json` output
+#[derive(Debug, Serialize)]
+struct KillReport {
+    schema: u32,
+    target: String,
+    dry_run: bool,
+    /// Signal sent, e.g. "HUP"; "TERM,KILL" for the default TERM-then-KILL
+    signal: String,
+    processes: Vec<KillOutcome>,
 }
 
-fn cmd_kill(entries: &[PortEntry], port: u16, force: bool) {
-    let found: Vec<_> = entries.iter().filter(|e| e.port == port).collect();
-    if found.is_empty() {
-        println!("No process found on port {port}");
-        return;
+#[derive(Debug, Serialize)]
+struct KillOutcome {
+    pid: u32,
+    process: String,
+    ports: Vec<u16>,
+    /// Whether the signal was sent; absent on a dry run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    killed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// "running" or "exited", checked after the kill (and the wait)
+    state: &'static str,
+    /// Time from sending the signal until the process was gone, with --wait
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_ms: Option<u64>,
+}
+
+#[cfg(unix)]
+fn describe_signal(signal: Option<i32>) -> String {
+    match signal.and_then(|sig| Signal::try_from(sig).ok()) {
+        Some(sig) => sig.as_str().trim_start_matches("SIG").to_string(),
+        None => "TERM,KILL".to_string(),
     }
+}
+
+#[cfg(not(unix))]
+fn describe_signal(_signal: Option<i32>) -> String {
+    "taskkill".to_string()
+}
 
+/// The processes `porty kill` acts on, once per PID, with their names
+fn kill_targets(found: &[&PortEntry], target: &KillTarget) -> Vec<(u32, String)> {
     // Deduplicate by PID to avoid killing the same process twice
     let mut target_pids: Vec<(u32, String)> = Vec::new();
     let mut seen_pids = std::collections::HashSet::new();
 
-    for entry in &found {
-        if let (Some(pid), Some(process)) = (entry.pid, &entry.process) {
-            if seen_pids.insert(pid) {
-                target_pids.push((pid, process.clone()));
+    for entry in found {
+        if let (Some(pid), Some(process)) = (entry.pid, &entry.process)
+            && seen_pids.insert(pid)
+        {
+            target_pids.push((pid, process.clone()));
+        }
+    }
+    // A PID given directly needn't be listening on anything
+    if let Some(pid) = target.pid
+        && target_pids.is_empty()
+    {
+        target_pids.push((pid, process_name(pid).unwrap_or_else(|| "unknown".to_string())));
+    }
+    target_pids
+}
+
+/// The owner of `pid` when that's another user. Without --all-users porty won't
+/// signal their processes, just as it doesn't list their sockets.
+fn other_owner(found: &[&PortEntry], pid: u32) -> Option<u32> {
+    let own = current_uid()?;
+    let owner = found
+        .iter()
+        .find(|e| e.pid == Some(pid))
+        .and_then(|e| e.uid)
+        .or_else(|| process_uid(pid))?;
+    (owner != own).then_some(owner)
+}
+
+/// Kill (unless it's a dry run) and record what happened to each process. The
+/// `bool` is `false` when a kill failed, another user's process was skipped or
+/// `--wait` timed out with some still running.
+fn kill_report(
+    found: &[&PortEntry],
+    target: &KillTarget,
+    force: bool,
+    signal: Option<i32>,
+    wait: Option<Duration>,
+) -> (KillReport, bool) {
+    let mut processes: Vec<KillOutcome> = kill_targets(found, target)
+        .into_iter()
+        .map(|(pid, process)| KillOutcome {
+            pid,
+            process,
+            ports: found.iter().filter(|e| e.pid == Some(pid)).map(|e| e.port).collect(),
+            killed: None,
+            error: other_owner(found, pid)
+                .filter(|_| !target.all_users)
+                .map(|owner| format!("owned by uid {owner}; pass --all-users to kill another user's process")),
+            state: "running",
+            exit_ms: None,
+        })
+        .collect();
+
+    let started = std::time::Instant::now();
+    if force {
+        for outcome in processes.iter_mut() {
+            if outcome.error.is_some() {
+                outcome.killed = Some(false);
+                continue;
+            }
+            let result = kill_pid(outcome.pid, signal);
+            outcome.killed = Some(result.is_ok());
+            outcome.error = result.err().map(|e| e.to_string());
+        }
+    }
+
+    let deadline = wait.filter(|_| force).map(|timeout| started + timeout);
+    loop {
+        for outcome in processes.iter_mut().filter(|o| o.state == "running") {
+            if !pid_alive(outcome.pid) {
+                outcome.state = "exited";
+                outcome.exit_ms = deadline.map(|_| started.elapsed().as_millis() as u64);
             }
         }
+        // Skipped processes won't go away on their own
+        let done = processes.iter().all(|o| o.state == "exited" || o.killed == Some(false));
+        if done || deadline.is_none_or(|deadline| std::time::Instant::now() >= deadline) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    // Skipped processes count as failed kills too
+    let failed = processes.iter().any(|o| o.killed == Some(false));
+    let success = !failed && (deadline.is_none() || processes.iter().all(|o| o.state == "exited"));
+    let report = KillReport {
+        schema: 1,
+        target: target.describe(),
+        dry_run: !force,
+        signal: describe_signal(signal),
+        processes,
+    };
+    (report, success)
+}
+
+/// Returns `false` when a kill failed, another user's process was skipped or
+/// `--wait` timed out with some of the processes still running
+fn cmd_kill(
+    entries: &[PortEntry],
+    target: &KillTarget,
+    force: bool,
+    signal: Option<i32>,
+    wait: Option<Duration>,
+    opts: &TableOptions,
+    out: &mut dyn Write,
+) -> bool {
+    let json = matches!(opts.format, OutputFormat::Json | OutputFormat::Ndjson);
+    let invalid = target.pid.and_then(|pid| {
+        if pid == std::process::id() {
+            Some(format!("Refusing to kill porty itself (PID {pid})"))
+        } else if !pid_alive(pid) {
+            Some(format!("No process with PID {pid}"))
+        } else {
+            None
+        }
+    });
+    if let Some(error) = invalid {
+        if json {
+            let _ = print_json(&serde_json::json!({ "schema": 1, "error": error }), opts.pretty, out);
+        } else {
+            eprintln!("{error}");
+        }
+        return false;
+    }
+
+    let found: Vec<_> = entries.iter().filter(|e| target.matches(e)).collect();
+    if json {
+        let (report, success) = kill_report(&found, target, force, signal, wait);
+        let _ = print_json(&report, opts.pretty, out);
+        return success;
+    }
+    if found.is_empty() && target.pid.is_none() {
+        println!("No process found for {}", target.describe());
+        return true;
     }
 
+    let target_pids = kill_targets(&found, target);
     if target_pids.is_empty() {
-        println!("No killable process found on port {port}");
-        return;
+        println!("No killable process found for {}", target.describe());
+        return true;
     }
 
     // Show what would be killed
-    println!("Process(es) on port {port}:");
+    println!("{} process(es) matched {}:", target_pids.len(), target.describe());
     for (pid, process) in &target_pids {
         println!("  {} (PID {})", process, pid);
+        if target.pid.is_some() {
+            let ports: Vec<String> = found.iter().map(|e| e.port.to_string()).collect();
+            if ports.is_empty() {
+                println!("    not listening on any TCP port");
+            } else {
+                println!("    listening on {}", ports.join(", "));
+            }
+        }
+        if let Some(owner) = other_owner(&found, *pid) {
+            if target.all_users {
+                eprintln!("    warning: owned by uid {owner}, not you; killing it will likely need sudo");
+            } else {
+                eprintln!("    owned by uid {owner}, not you; it won't be killed without --all-users");
+            }
+        }
+        // Show the full command so the right one of several `node`s gets killed
+        if !force && let Some(command) = get_command_line(*pid) {
+            println!("    {}", command);
+        }
     }
 
     if !force {
         println!("\nDry run mode. Use --force to actually kill the process(es).");
-        println!("Example: porty kill {} --force", port);
-        return;
+        match signal {
+            Some(sig) => println!("Example: porty kill {} --force --signal {}", target.args(), sig),
+            None => println!("Example: porty kill {} --force", target.args()),
+        }
+        return true;
     }
 
     // Actually kill with --force
-    println!("\nKilling process(es)...");
+    // Another user's process needs --all-users, like listing its sockets does
+    let (skipped, target_pids): (Vec<_>, Vec<_>) = target_pids
+        .into_iter()
+        .partition(|(pid, _)| !target.all_users && other_owner(&found, *pid).is_some());
+    for (pid, process) in &skipped {
+        eprintln!("Skipping {} (PID {}): owned by another user; pass --all-users to kill it", process, pid);
+    }
+    if target_pids.is_empty() {
+        return false;
+    }
+    // Signals like HUP ask the process to do something rather than to exit
+    let other_signal = signal.map(|_| describe_signal(signal)).filter(|name| name != "TERM" && name != "KILL");
+    match other_signal {
+        Some(ref name) => println!("\nSending SIG{name}..."),
+        None => println!("\nKilling process(es)..."),
+    }
+    let mut failed = !skipped.is_empty();
+    let mut signalled = Vec::new();
     for (pid, process) in target_pids {
-        println!("Killing {} (PID {})...", process, pid);
-        match kill_pid(pid) {
-            Ok(_) => println!("Process killed"),
-            Err(e) => eprintln!("Failed to kill process: {}", e),
+        match other_signal {
+            Some(ref name) => println!("Sending SIG{} to {} (PID {})...", name, process, pid),
+            None => println!("Killing {} (PID {})...", process, pid),
+        }
+        match (kill_pid(pid, signal), &other_signal) {
+            (Ok(_), Some(name)) => println!("Sent SIG{name}"),
+            (Ok(_), None) => println!("Process killed"),
+            (Err(e), _) => {
+                eprintln!("Failed to kill process: {}", e);
+                failed = true;
+                continue;
+            }
+        }
+        signalled.push((pid, process));
+    }
+
+    let Some(timeout) = wait else {
+        return !failed;
+    };
+    let deadline = std::time::Instant::now() + timeout;
+    // A process that couldn't be signalled won't exit because of us
+    let mut remaining = signalled;
+    loop {
+        remaining.retain(|(pid, _)| pid_alive(*pid));
+        if remaining.is_empty() {
+            println!("All targeted processes have exited");
+            return !failed;
+        }
+        if std::time::Instant::now() >= deadline {
+            break;
         }
+        thread::sleep(Duration::from_millis(100));
     }
+    eprintln!("Timed out after {}s; still running:", format_float(timeout.as_secs_f64(), 1));
+    for (pid, process) in &remaining {
+        eprintln!("  {} (PID {})", process, pid);
+    }
+    false
 }
 
 #[cfg(target_os = "macos")]
-fn get_detailed_port_info(port: u16, pid: u32, kind: Kind) -> Result<DetailedPortInfo> {
+fn get_detailed_port_info(
+    port: u16,
+    pid: u32,
+    kind: Kind,
+    docker: &DockerSnapshot,
+    opts: &DetailOptions,
+) -> Result<DetailedPortInfo> {
     use std::thread;
     
     let process_name = get_process_name_libproc(pid).unwrap_or_else(|| "unknown".to_string());
@@ -338,9 +4242,7 @@ fn get_detailed_port_info(port: u16, pid: u32, kind: Kind) -> Result<DetailedPor
     // Run expensive operations in parallel
     let pid_for_ps = pid;
     let pid_for_lsof = pid;
-    let pid_for_children = pid;
     let port_for_connections = port;
-    let process_name_for_docker = process_name.clone();
     
     // Thread 1: Combined ps call for all process info
     let ps_handle = thread::spawn(move || {
@@ -352,66 +4254,271 @@ fn get_detailed_port_info(port: u16, pid: u32, kind: Kind) -> Result<DetailedPor
         get_combined_lsof_info(pid_for_lsof, port)
     });
     
-    // Thread 3: Parent chain (requires multiple calls)
-    let parent_handle = thread::spawn(move || {
-        get_parent_chain(pid)
+    // Thread 3: Process tree, walked in one snapshot of the process table
+    let tree_handle = thread::spawn(move || {
+        let table = get_process_table();
+        let chain = get_parent_chain(pid, &table);
+        // The chain leaves out init/launchd, which is still the direct parent
+        let parent_pid = table.get(&pid).map(|(ppid, _)| *ppid);
+        let children = get_child_processes(pid, &table);
+        (chain, parent_pid, get_session_leader(pid), children)
     });
     
-    // Thread 4: Child processes
-    let children_handle = thread::spawn(move || {
-        get_child_processes(pid_for_children)
-    });
-    
-    // Thread 5: Active connections
+    // Thread 4: Active connections
     let connections_handle = thread::spawn(move || {
-        count_active_connections(port_for_connections)
+        get_connection_peers(port_for_connections)
     });
     
-    // Thread 6: Docker info (only if it looks like a container)
+    // Thread 5: Docker info (only if it looks like a container)
+    let containers = if is_docker_process(Some(&process_name), exec_path.as_deref()) {
+        docker.containers().to_vec()
+    } else {
+        Vec::new()
+    };
     let docker_handle = thread::spawn(move || {
-        get_docker_info(port_for_connections, &process_name_for_docker)
+        get_docker_info(port_for_connections, &containers)
+    });
+    
+    // Thread 6: Listen queue sizes
+    let queue_handle = thread::spawn(move || {
+        get_listen_queue(port_for_connections)
+    });
+    
+    // Thread 7: Throughput sampling (opt-in, takes about a second)
+    let sample_traffic = opts.traffic;
+    let traffic_handle = thread::spawn(move || {
+        if sample_traffic { sample_traffic_nettop(pid) } else { None }
     });
     
     // Collect results
-    let ps_info = ps_handle.join().unwrap_or_default();
-    let lsof_info = lsof_handle.join().unwrap_or_default();
-    let parent_chain = parent_handle.join().unwrap_or_default();
-    let children = children_handle.join().unwrap_or_default();
-    let active_connections = connections_handle.join().unwrap_or(0);
-    let docker_info = docker_handle.join().unwrap_or(None);
+    let debug = opts.debug;
+    let ps_info = join_or_default("ps", ps_handle, debug);
+    let lsof_info = join_or_default("lsof", lsof_handle, debug);
+    let (parent_chain, parent_pid, session_leader, children) = join_or_default("process tree", tree_handle, debug);
+    let peer_addresses = join_or_default("connections", connections_handle, debug);
+    let hosts = if opts.resolve_dns { resolve_peer_hosts(&peer_addresses) } else { Default::default() };
+    let peers: Vec<Peer> = peer_addresses
+        .into_iter()
+        .map(|address| {
+            let host = hosts.get(&address).cloned().flatten();
+            Peer { address, host }
+        })
+        .collect();
+    let active_connections = peers.len() as u32;
+    let docker_info = join_or_default("docker", docker_handle, debug);
+    let listen_queue = join_or_default("listen queue", queue_handle, debug);
+    let traffic = join_or_default("traffic", traffic_handle, debug);
+
+    let command = ps_info.command.unwrap_or_else(|| "unknown".to_string());
+    let forwarded_by = if kind == Kind::Forward {
+        find_forward_command(&command, parent_pid)
+    } else {
+        None
+    };
+    let mut argv = split_command_line(&command).into_iter();
+    let exec = argv.next().unwrap_or_else(|| command.clone());
+    let args: Vec<String> = argv.collect();
+    let started_at = parse_lstart(&ps_info.start_time);
+    let start_time = match started_at {
+        Some(t) => format_start_time(&ps_info.start_time, t, opts.time_format),
+        None => ps_info.start_time,
+    };
+    let brew_service = exec_path.as_deref().and_then(brew_service_name);
+    let version_manager = exec_path
+        .as_deref()
+        .and_then(version_manager_from_path)
+        .or_else(|| version_manager_from_parents(&parent_chain, &process_name));
+    let reachable_at = reachable_address(port, &lsof_info.listen_addresses);
+    let kube_forward = if process_name.contains("kubectl") {
+        parse_kubectl_port_forward(&command)
+    } else {
+        None
+    };
 
     Ok(DetailedPortInfo {
         port,
         pid,
         process_name,
-        command: ps_info.command.unwrap_or_else(|| "unknown".to_string()),
+        command,
+        exec,
+        args,
         working_dir: lsof_info.working_dir,
         exec_path,
         user_name: ps_info.user_name,
         uid: ps_info.uid,
+        state: ps_info.state,
         parent_chain,
+        parent_pid,
+        session_leader,
         children,
         uptime: ps_info.uptime,
-        start_time: ps_info.start_time,
+        listening_since: start_time.clone(),
+        note: None,
+        start_time,
+        started_at,
         memory_rss: ps_info.memory_rss,
         memory_virtual: ps_info.memory_virtual,
         cpu_usage: ps_info.cpu_usage,
         thread_count: ps_info.thread_count,
         file_descriptors: lsof_info.file_descriptors,
+        fd_breakdown: lsof_info.fd_breakdown,
         listen_addresses: lsof_info.listen_addresses,
+        reachable_at,
         active_connections,
+        peers,
         other_ports: lsof_info.other_ports,
         env_vars: ps_info.env_vars,
         kind,
         docker_info,
+        listen_queue,
+        traffic,
+        forwarded_by,
+        kube_forward,
+        brew_service,
+        version_manager,
+    })
+}
+
+/// Where another host on the LAN reaches a wildcard-bound port: the address of
+/// the interface the default route goes out of. IPv4 unless the socket is IPv6-only.
+#[cfg(target_os = "macos")]
+fn reachable_address(port: u16, addresses: &[(IpFamily, String)]) -> Option<String> {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+    let wildcard = |family: IpFamily| {
+        addresses
+            .iter()
+            .any(|(f, addr)| *f == family && matches!(address_host(addr), "*" | "0.0.0.0" | "::"))
+    };
+    let (local, remote): (IpAddr, IpAddr) = if wildcard(IpFamily::V4) {
+        (Ipv4Addr::UNSPECIFIED.into(), Ipv4Addr::new(192, 0, 2, 1).into())
+    } else if wildcard(IpFamily::V6) {
+        (Ipv6Addr::UNSPECIFIED.into(), Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).into())
+    } else {
+        return None;
+    };
+    // Connecting a UDP socket only picks a route; no packet is sent
+    let socket = UdpSocket::bind(SocketAddr::new(local, 0)).ok()?;
+    socket.connect(SocketAddr::new(remote, 9)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then(|| SocketAddr::new(ip, port).to_string())
+}
+
+/// Join a helper thread, degrading to an empty result if it panicked.
+/// With `--debug` the failing helper and its panic message go to stderr.
+#[cfg(target_os = "macos")]
+fn join_or_default<T: Default>(name: &str, handle: thread::JoinHandle<T>, debug: bool) -> T {
+    handle.join().unwrap_or_else(|payload| {
+        if debug {
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            eprintln!("debug: {name} helper failed: {reason}");
+        }
+        T::default()
     })
 }
 
+/// Find the command line that set up a forward: the ssh client or kubectl
+/// itself when it was given its forward arguments, otherwise its parent's from
+/// the process table (e.g. the script that started a tunnel)
+#[cfg(target_os = "macos")]
+fn find_forward_command(command: &str, parent_pid: Option<u32>) -> Option<String> {
+    let has_forward_flag = command
+        .split_whitespace()
+        .any(|arg| arg.starts_with("-L") || arg.starts_with("-R") || arg.starts_with("-D"));
+    if has_forward_flag || command.contains("port-forward") {
+        return Some(command.to_string());
+    }
+
+    parent_pid.and_then(get_command_line)
+}
+
+/// Split a command line into words the way a POSIX shell would: whitespace
+/// separates words, quotes group them and a backslash escapes the next character
+#[cfg(target_os = "macos")]
+fn split_command_line(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    word.push(escaped);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Full command line of a process, as reported by `ps -o command=`
+#[cfg(unix)]
+fn get_command_line(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "command="])
+        .output_within()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let command = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!command.is_empty()).then_some(command)
+}
+
+#[cfg(not(unix))]
+fn get_command_line(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Command lines of every process, from a single `ps` call
+#[cfg(target_os = "macos")]
+fn get_all_command_lines() -> std::collections::HashMap<u32, String> {
+    let Ok(output) = std::process::Command::new("ps").args(["-axo", "pid=,command="]).output_within() else {
+        return std::collections::HashMap::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim_start().split_once(char::is_whitespace)?;
+            Some((pid.parse().ok()?, command.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
 #[derive(Default)]
 struct CombinedPsInfo {
     command: Option<String>,
     user_name: String,
     uid: u32,
+    state: Option<ProcessState>,
     uptime: String,
     start_time: String,
     memory_rss: u64,
@@ -421,14 +4528,55 @@ struct CombinedPsInfo {
     env_vars: Vec<(String, String)>,
 }
 
+#[cfg(target_os = "macos")]
 #[derive(Default)]
 struct CombinedLsofInfo {
     working_dir: Option<String>,
     file_descriptors: u32,
-    listen_addresses: Vec<String>,
+    fd_breakdown: FdBreakdown,
+    listen_addresses: Vec<(IpFamily, String)>,
     other_ports: Vec<u16>,
 }
 
+#[cfg(not(target_os = "macos"))]
+fn get_detailed_port_info(
+    _port: u16,
+    _pid: u32,
+    _kind: Kind,
+    _docker: &DockerSnapshot,
+    _opts: &DetailOptions,
+) -> Result<DetailedPortInfo> {
+    Err(anyhow::anyhow!("detailed port info is only available on macOS"))
+}
+
+/// Parse `ps -o lstart=` output ("Thu Jan 23 14:23:15 2026") as local time
+#[cfg(target_os = "macos")]
+fn parse_lstart(raw: &str) -> Option<DateTime<Local>> {
+    use chrono::{NaiveDateTime, TimeZone};
+
+    // ps pads single-digit days with an extra space
+    let normalized = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    let naive = NaiveDateTime::parse_from_str(&normalized, "%a %b %d %H:%M:%S %Y").ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+#[cfg(target_os = "macos")]
+fn format_start_time(raw: &str, started: DateTime<Local>, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::Absolute => raw.to_string(),
+        TimeFormat::Iso => started.to_rfc3339(),
+        TimeFormat::Relative => {
+            let secs = (Local::now() - started).num_seconds().max(0);
+            match secs {
+                0..60 => format!("{}s ago", secs),
+                60..3600 => format!("{}m ago", secs / 60),
+                3600..86400 => format!("{}h ago", secs / 3600),
+                _ => format!("{}d ago", secs / 86400),
+            }
+        }
+    }
+}
+
 #[cfg(target_os = "macos")]
 fn get_combined_ps_info(pid: u32) -> CombinedPsInfo {
     use std::process::Command;
@@ -438,50 +4586,34 @@ fn get_combined_ps_info(pid: u32) -> CombinedPsInfo {
     info.uptime = "unknown".to_string();
     info.start_time = "unknown".to_string();
     
-    // Single ps call for most info: command, user, uid, rss, vsz, %cpu, etime, lstart
+    // Single ps call for most info; none of these fields contain spaces
     let output = Command::new("ps")
-        .args(["-p", &pid.to_string(), "-o", "command=,user=,uid=,rss=,vsz=,%cpu=,etime="])
-        .output();
+        .args(["-p", &pid.to_string(), "-o", "user=,uid=,state=,rss=,vsz=,%cpu=,etime="])
+        .output_within();
     
     if let Ok(output) = output {
         if output.status.success() {
-            let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            // Parse from the end since command can contain spaces
-            let parts: Vec<&str> = text.rsplitn(7, char::is_whitespace).collect();
-            if parts.len() >= 6 {
-                info.uptime = parts[0].trim().to_string();
-                info.cpu_usage = parts[1].trim().parse().unwrap_or(0.0);
-                info.memory_virtual = parts[2].trim().parse().unwrap_or(0);
-                info.memory_rss = parts[3].trim().parse().unwrap_or(0);
-                info.uid = parts[4].trim().parse().unwrap_or(0);
-                info.user_name = parts[5].trim().to_string();
-                // Command is everything before these fields
-                if parts.len() >= 7 {
-                    let cmd_parts: Vec<&str> = parts[6..].iter().rev().map(|s| *s).collect();
-                    info.command = Some(cmd_parts.join(" "));
-                }
+            let text = String::from_utf8_lossy(&output.stdout);
+            let parts: Vec<&str> = text.split_whitespace().collect();
+            if let [user, uid, state, rss, vsz, cpu, etime] = parts[..] {
+                info.user_name = user.to_string();
+                info.uid = uid.parse().unwrap_or(0);
+                info.state = ProcessState::from_ps(state);
+                info.memory_rss = rss.parse().unwrap_or(0);
+                info.memory_virtual = vsz.parse().unwrap_or(0);
+                info.cpu_usage = cpu.parse().unwrap_or(0.0);
+                info.uptime = etime.to_string();
             }
         }
     }
     
-    // Get full command separately (the above parsing can be tricky)
-    let cmd_output = Command::new("ps")
-        .args(["-p", &pid.to_string(), "-o", "command="])
-        .output();
-    
-    if let Ok(output) = cmd_output {
-        if output.status.success() {
-            let cmd = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !cmd.is_empty() {
-                info.command = Some(cmd);
-            }
-        }
-    }
+    // The command line has its own call since it contains spaces
+    info.command = get_command_line(pid);
     
     // Get lstart (start time) separately since it has spaces
     let lstart_output = Command::new("ps")
         .args(["-p", &pid.to_string(), "-o", "lstart="])
-        .output();
+        .output_within();
     
     if let Ok(output) = lstart_output {
         if output.status.success() {
@@ -492,7 +4624,7 @@ fn get_combined_ps_info(pid: u32) -> CombinedPsInfo {
     // Get thread count
     let thread_output = Command::new("ps")
         .args(["-p", &pid.to_string(), "-M"])
-        .output();
+        .output_within();
     
     if let Ok(output) = thread_output {
         if output.status.success() {
@@ -515,15 +4647,47 @@ fn get_combined_lsof_info(pid: u32, current_port: u16) -> CombinedLsofInfo {
     
     // Single lsof call for all file info
     let output = Command::new("lsof")
-        .args(["-p", &pid.to_string(), "-Fn"])
-        .output();
+        .args(["-p", &pid.to_string(), "-FftPn"])
+        .output_within();
     
     if let Ok(output) = output {
         if output.status.success() {
             let text = String::from_utf8_lossy(&output.stdout);
             let mut ports_seen = std::collections::HashSet::new();
+            let mut fds_seen = std::collections::HashSet::new();
+            let mut current_family = IpFamily::V4;
+            let mut current_tcp = false;
+            let mut current_fd_new = false;
             
             for line in text.lines() {
+                if let Some(fd) = line.strip_prefix('f') {
+                    // Only numbered descriptors are open files; cwd, txt, mem etc. are mappings
+                    current_fd_new = fd.starts_with(|c: char| c.is_ascii_digit())
+                        && fds_seen.insert(fd.to_string());
+                    continue;
+                }
+                
+                if let Some(file_type) = line.strip_prefix('t') {
+                    // Socket type of the file whose name follows
+                    current_family = if file_type == "IPv6" { IpFamily::V6 } else { IpFamily::V4 };
+                    current_tcp = false;
+                    if current_fd_new {
+                        let counts = &mut info.fd_breakdown;
+                        match file_type {
+                            "IPv4" | "IPv6" | "unix" | "sock" => counts.sockets += 1,
+                            "REG" => counts.files += 1,
+                            "PIPE" | "FIFO" => counts.pipes += 1,
+                            _ => counts.other += 1,
+                        }
+                    }
+                    continue;
+                }
+                
+                if let Some(protocol) = line.strip_prefix('P') {
+                    current_tcp = protocol == "TCP";
+                    continue;
+                }
+                
                 if line.starts_with('n') {
                     let value = &line[1..];
                     
@@ -536,17 +4700,18 @@ fn get_combined_lsof_info(pid: u32, current_port: u16) -> CombinedLsofInfo {
                     if value.contains(':') {
                         if let Some(port) = extract_port(value) {
                             if port == current_port {
-                                info.listen_addresses.push(value.to_string());
-                            } else {
+                                info.listen_addresses.push((current_family, normalize_address(current_family, value)));
+                            } else if current_tcp && !value.contains("->") {
+                                // Connected sockets carry "local->remote"; only
+                                // unconnected TCP sockets are listeners
                                 ports_seen.insert(port);
                             }
                         }
                     }
                 }
-                
-                info.file_descriptors += 1;
             }
             
+            info.file_descriptors = fds_seen.len() as u32;
             info.other_ports = ports_seen.into_iter().collect();
             info.other_ports.sort();
         }
@@ -555,7 +4720,7 @@ fn get_combined_lsof_info(pid: u32, current_port: u16) -> CombinedLsofInfo {
     // Get working directory with specific lsof call (more reliable)
     let cwd_output = Command::new("lsof")
         .args(["-p", &pid.to_string(), "-a", "-d", "cwd", "-Fn"])
-        .output();
+        .output_within();
     
     if let Ok(output) = cwd_output {
         if output.status.success() {
@@ -572,351 +4737,974 @@ fn get_combined_lsof_info(pid: u32, current_port: u16) -> CombinedLsofInfo {
     info
 }
 
+/// Parent PID and name of every process, from a single `ps` call
+#[cfg(target_os = "macos")]
+fn get_process_table() -> std::collections::HashMap<u32, (u32, String)> {
+    let Ok(output) = std::process::Command::new("ps").args(["-axo", "pid=,ppid=,comm="]).output_within() else {
+        return std::collections::HashMap::new();
+    };
+    // "  412     1 /usr/sbin/sshd"; comm is a path that may contain spaces
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid: u32 = fields.next()?.parse().ok()?;
+            let ppid: u32 = fields.next()?.parse().ok()?;
+            let comm = fields.collect::<Vec<_>>().join(" ");
+            let name = comm.rsplit('/').next().unwrap_or(&comm).to_string();
+            Some((pid, (ppid, name)))
+        })
+        .collect()
+}
+
+/// A process from the table, named the way the rest of the detailed view names
+/// processes; the `ps` name covers processes that exited since the snapshot
+#[cfg(target_os = "macos")]
+fn process_ref(pid: u32, table_name: &str) -> ProcessRef {
+    let name = get_process_name_libproc(pid).unwrap_or_else(|| table_name.to_string());
+    ProcessRef { pid, name }
+}
+
+/// Ancestors of `pid`, oldest first, up to but excluding init/launchd
 #[cfg(target_os = "macos")]
-fn get_parent_chain(pid: u32) -> Vec<(u32, String)> {
+fn get_parent_chain(pid: u32, table: &std::collections::HashMap<u32, (u32, String)>) -> Vec<ProcessRef> {
     let mut chain = Vec::new();
     let mut current_pid = pid;
     let mut seen = std::collections::HashSet::new();
     
     // Limit to 10 levels to avoid infinite loops
     for _ in 0..10 {
-        if seen.contains(&current_pid) {
+        if !seen.insert(current_pid) {
             break;
         }
-        seen.insert(current_pid);
-        
-        if let Some(parent_pid) = get_parent_pid(current_pid) {
-            if parent_pid == 0 || parent_pid == 1 {
-                break;
-            }
-            if let Some(name) = get_process_name_libproc(parent_pid) {
-                chain.insert(0, (parent_pid, name));
-                current_pid = parent_pid;
-            } else {
-                break;
-            }
-        } else {
+        let Some((parent_pid, _)) = table.get(&current_pid) else {
+            break;
+        };
+        if *parent_pid == 0 || *parent_pid == 1 {
             break;
         }
+        let Some((_, name)) = table.get(parent_pid) else {
+            break;
+        };
+        chain.insert(0, process_ref(*parent_pid, name));
+        current_pid = *parent_pid;
     }
     
     chain
 }
 
+/// The process leading `pid`'s session, when it's still running
 #[cfg(target_os = "macos")]
-fn get_parent_pid(pid: u32) -> Option<u32> {
-    use std::process::Command;
-    let output = Command::new("ps")
-        .args(["-p", &pid.to_string(), "-o", "ppid="])
-        .output()
-        .ok()?;
-    
-    if output.status.success() {
-        String::from_utf8_lossy(&output.stdout)
-            .trim()
-            .parse()
-            .ok()
-    } else {
-        None
+fn get_session_leader(pid: u32) -> Option<ProcessRef> {
+    let sid = unsafe { libc::getsid(pid as libc::pid_t) };
+    if sid <= 0 {
+        return None;
     }
+    let sid = sid as u32;
+    let name = get_process_name_libproc(sid)?;
+    Some(ProcessRef { pid: sid, name })
+}
+
+#[cfg(target_os = "macos")]
+fn get_child_processes(pid: u32, table: &std::collections::HashMap<u32, (u32, String)>) -> Vec<ProcessRef> {
+    let mut children: Vec<ProcessRef> = table
+        .iter()
+        .filter(|(_, (ppid, _))| *ppid == pid)
+        .map(|(child_pid, (_, name))| process_ref(*child_pid, name))
+        .collect();
+    children.sort_by_key(|child| child.pid);
+    children
 }
 
+/// Remote addresses of the established connections to `port`
 #[cfg(target_os = "macos")]
-fn get_child_processes(pid: u32) -> Vec<(u32, String)> {
+fn get_connection_peers(port: u16) -> Vec<String> {
     use std::process::Command;
-    let output = Command::new("pgrep")
-        .args(["-P", &pid.to_string()])
-        .output();
-    
-    let Ok(output) = output else {
+    let Ok(output) = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:ESTABLISHED", "-Fn"])
+        .output_within()
+    else {
         return Vec::new();
     };
-    
-    if !output.status.success() {
-        return Vec::new();
-    }
-    
+
+    // n127.0.0.1:3000->127.0.0.1:54321; skip our own outgoing connections to
+    // other hosts' port 3000, and count sockets shared by forked workers once
     let text = String::from_utf8_lossy(&output.stdout);
-    let mut children = Vec::new();
-    
-    for line in text.lines() {
-        if let Ok(child_pid) = line.trim().parse::<u32>() {
-            if let Some(name) = get_process_name_libproc(child_pid) {
-                children.push((child_pid, name));
+    let mut seen = std::collections::HashSet::new();
+    text.lines()
+        .filter_map(|line| line.strip_prefix('n'))
+        .filter(|name| seen.insert(*name))
+        .filter_map(|name| name.split_once("->"))
+        .filter(|(local, _)| extract_port(local) == Some(port))
+        .map(|(_, remote)| remote.to_string())
+        .collect()
+}
+
+/// How long `--resolve-dns` waits for reverse lookups
+#[cfg(target_os = "macos")]
+const DNS_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Reverse DNS names of the peers' IPs. Lookups run in parallel, give up after
+/// `DNS_TIMEOUT`, and are remembered for the rest of the run (e.g. across
+/// `--watch` refreshes).
+#[cfg(target_os = "macos")]
+fn resolve_peer_hosts(peers: &[String]) -> std::collections::HashMap<String, Option<String>> {
+    use std::collections::HashMap;
+    use std::net::IpAddr;
+    use std::sync::{Mutex, OnceLock, mpsc};
+
+    static CACHE: OnceLock<Mutex<HashMap<IpAddr, Option<String>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+
+    let ips: std::collections::HashSet<IpAddr> = peers
+        .iter()
+        .filter_map(|peer| address_host(peer).parse().ok())
+        .collect();
+    let pending: Vec<IpAddr> = {
+        let cache = cache.lock().unwrap();
+        ips.into_iter().filter(|ip| !cache.contains_key(ip)).collect()
+    };
+
+    let (tx, rx) = mpsc::channel();
+    for ip in &pending {
+        let (tx, ip) = (tx.clone(), *ip);
+        // Detached: a lookup stuck past the timeout just finishes unseen
+        thread::spawn(move || {
+            let _ = tx.send((ip, reverse_dns(ip)));
+        });
+    }
+    drop(tx);
+    let deadline = std::time::Instant::now() + DNS_TIMEOUT;
+    while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+        match rx.recv_timeout(left) {
+            Ok((ip, host)) => {
+                cache.lock().unwrap().insert(ip, host);
             }
+            Err(_) => break,
         }
     }
-    
-    children
+
+    let cache = cache.lock().unwrap();
+    peers
+        .iter()
+        .map(|peer| {
+            let host = address_host(peer).parse().ok().and_then(|ip| cache.get(&ip).cloned().flatten());
+            (peer.clone(), host)
+        })
+        .collect()
+}
+
+/// The name an IP reverse-resolves to, if it has one
+#[cfg(target_os = "macos")]
+fn reverse_dns(ip: std::net::IpAddr) -> Option<String> {
+    use nix::sys::socket::{SockaddrLike, SockaddrStorage};
+
+    let addr = SockaddrStorage::from(std::net::SocketAddr::new(ip, 0));
+    let mut host = [0 as libc::c_char; 1025]; // NI_MAXHOST
+    let rc = unsafe {
+        libc::getnameinfo(
+            addr.as_ptr(),
+            addr.len(),
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if rc != 0 {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(host.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
 }
 
+/// Working directory of each process, from a single lsof run
 #[cfg(target_os = "macos")]
-fn count_active_connections(port: u16) -> u32 {
+fn get_working_dirs(pids: &[u32]) -> std::collections::HashMap<u32, String> {
     use std::process::Command;
-    let output = Command::new("lsof")
-        .args(["-iTCP", &format!(":{}", port), "-sTCP:ESTABLISHED", "-t"])
-        .output();
-    
-    if let Ok(output) = output {
-        if output.status.success() {
-            let text = String::from_utf8_lossy(&output.stdout);
-            return text.lines().count() as u32;
+
+    let mut dirs = std::collections::HashMap::new();
+    if pids.is_empty() {
+        return dirs;
+    }
+    let list = pids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+    let Ok(output) = Command::new("lsof").args(["-a", "-d", "cwd", "-Fpn", "-p", &list]).output_within() else {
+        return dirs;
+    };
+
+    // p<pid> then n<path>; lsof exits non-zero if some PIDs are gone, so the
+    // status is ignored
+    let mut current_pid = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(pid) = line.strip_prefix('p') {
+            current_pid = pid.parse::<u32>().ok();
+        } else if let Some(path) = line.strip_prefix('n')
+            && let Some(pid) = current_pid
+        {
+            dirs.insert(pid, path.to_string());
+        }
+    }
+    dirs
+}
+
+/// Resident memory (KB) and CPU usage (%) of each process, from a single ps run
+#[cfg(unix)]
+fn get_resource_usage(pids: &[u32]) -> std::collections::HashMap<u32, (u64, f64)> {
+    let mut usage = std::collections::HashMap::new();
+    if pids.is_empty() {
+        return usage;
+    }
+    let list = pids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+    let Ok(output) = std::process::Command::new("ps").args(["-o", "pid=,rss=,%cpu=", "-p", &list]).output_within() else {
+        return usage;
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if let [pid, rss, cpu] = fields[..]
+            && let (Ok(pid), Ok(rss), Ok(cpu)) = (pid.parse(), rss.parse(), cpu.parse())
+        {
+            usage.insert(pid, (rss, cpu));
         }
     }
-    0
+    usage
+}
+
+#[cfg(not(unix))]
+fn get_resource_usage(_pids: &[u32]) -> std::collections::HashMap<u32, (u64, f64)> {
+    std::collections::HashMap::new()
+}
+
+/// Windows has no cheap way to read another process's working directory
+#[cfg(not(target_os = "macos"))]
+fn get_working_dirs(_pids: &[u32]) -> std::collections::HashMap<u32, String> {
+    std::collections::HashMap::new()
+}
+
+/// A path with the home directory shown as `~`
+fn tilde_path(path: &str) -> String {
+    let home = std::env::var("HOME").unwrap_or_default();
+    match path.strip_prefix(home.as_str()) {
+        Some(rest) if !home.is_empty() && (rest.is_empty() || rest.starts_with('/')) => format!("~{rest}"),
+        _ => path.to_string(),
+    }
 }
 
+/// Established connections per local port, from a single lsof run
 #[cfg(target_os = "macos")]
-fn get_environment_variables(pid: u32) -> Vec<(String, String)> {
+fn count_established_by_port() -> std::collections::HashMap<u16, u32> {
     use std::process::Command;
-    
-    // Get important environment variables
-    let important_vars = vec![
-        "NODE_ENV", "PORT", "DATABASE_URL", "RAILS_ENV", "FLASK_ENV",
-        "DJANGO_SETTINGS_MODULE", "PYTHON_ENV", "GO_ENV", "RUST_ENV",
-        "PATH", "HOME", "USER", "PWD", "LANG"
-    ];
-    
-    let output = Command::new("ps")
-        .args(["eww", &pid.to_string()])
-        .output();
-    
-    let Ok(output) = output else {
-        return Vec::new();
+
+    let mut counts = std::collections::HashMap::new();
+    let Ok(output) = Command::new("lsof").args(["-nP", "-iTCP", "-sTCP:ESTABLISHED", "-Fn"]).output_within() else {
+        return counts;
+    };
+
+    // n127.0.0.1:3000->127.0.0.1:54321; a socket shared by several processes
+    // (e.g. forked workers) is listed once per process but counted once
+    let text = String::from_utf8_lossy(&output.stdout);
+    let sockets: std::collections::HashSet<&str> = text
+        .lines()
+        .filter_map(|line| line.strip_prefix('n'))
+        .collect();
+    for name in sockets {
+        if let Some((local, _)) = name.split_once("->")
+            && let Some(port) = extract_port(local)
+        {
+            *counts.entry(port).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Established connections per local port, from a single netstat run
+#[cfg(windows)]
+fn count_established_by_port() -> std::collections::HashMap<u16, u32> {
+    use std::process::Command;
+
+    let mut counts = std::collections::HashMap::new();
+    let Ok(output) = Command::new("netstat").args(["-an", "-p", "TCP"]).output_within() else {
+        return counts;
     };
+
+    //   TCP    127.0.0.1:3000    127.0.0.1:54321    ESTABLISHED
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() >= 4
+            && fields[3] == "ESTABLISHED"
+            && let Some(port) = extract_port(fields[1])
+        {
+            *counts.entry(port).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn count_established_by_port() -> std::collections::HashMap<u16, u32> {
+    std::collections::HashMap::new()
+}
+
+#[cfg(target_os = "macos")]
+fn get_listen_queue(port: u16) -> Option<ListenQueue> {
+    use std::process::Command;
+    let output = Command::new("netstat")
+        .args(["-L", "-an", "-p", "tcp"])
+        .output_within()
+        .ok()?;
     
     if !output.status.success() {
-        return Vec::new();
+        return None;
     }
     
+    // Output format (the leading protocol column is missing on some versions):
+    //   Current listen queue sizes (qlen/incqlen/maxqlen)
+    //   Listen         Local Address
+    //   0/0/128        *.3000
+    //   0/0/128        127.0.0.1.5432
     let text = String::from_utf8_lossy(&output.stdout);
-    let mut env_vars = Vec::new();
+    let mut best: Option<ListenQueue> = None;
     
-    // Parse ps eww output which shows environment variables
-    // Format is: "VAR1=value1 VAR2=value2 ..."
     for line in text.lines() {
-        for part in line.split_whitespace() {
-            if let Some(eq_pos) = part.find('=') {
-                let key = &part[..eq_pos];
-                let value = &part[eq_pos + 1..];
-                
-                // Only keep important vars to avoid clutter
-                if important_vars.contains(&key) {
-                    env_vars.push((key.to_string(), value.to_string()));
-                }
-            }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(idx) = fields.iter().position(|f| f.matches('/').count() == 2) else {
+            continue;
+        };
+        let Some(addr) = fields.get(idx + 1) else {
+            continue;
+        };
+        
+        // netstat separates the port with a dot: "127.0.0.1.5432", "*.3000", "::1.5432"
+        let addr_port = addr.rsplit('.').next().and_then(|p| p.parse::<u16>().ok());
+        if addr_port != Some(port) {
+            continue;
+        }
+        
+        let sizes: Vec<u32> = fields[idx].split('/').filter_map(|n| n.parse().ok()).collect();
+        if sizes.len() != 3 {
+            continue;
+        }
+        
+        let queue = ListenQueue { queued: sizes[0], incomplete: sizes[1], max: sizes[2], overflows: None };
+        // With several sockets on the port (IPv4 + IPv6), report the fullest one
+        if best.is_none_or(|b| queue.queued > b.queued) {
+            best = Some(queue);
         }
     }
     
-    env_vars
+    let mut best = best?;
+    best.overflows = count_listen_overflows();
+    Some(best)
 }
 
+/// Listen queue overflows since boot, from the TCP statistics:
+///     12 listen queue overflows
 #[cfg(target_os = "macos")]
-fn get_docker_info(port: u16, process_name: &str) -> Option<DockerInfo> {
-    // Only check if this looks like a Docker process
-    if !process_name.to_lowercase().contains("docker") 
-        && !process_name.to_lowercase().contains("com.docker") {
+fn count_listen_overflows() -> Option<u64> {
+    let output = std::process::Command::new("netstat").args(["-s", "-p", "tcp"]).output_within().ok()?;
+    if !output.status.success() {
         return None;
     }
-    
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("listen queue overflow"))
+        .and_then(|line| line.split_whitespace().next()?.parse().ok())
+}
+
+#[cfg(target_os = "macos")]
+fn sample_traffic_nettop(pid: u32) -> Option<Traffic> {
     use std::process::Command;
-    let output = Command::new("docker")
-        .args(["ps", "--format", "{{.ID}}|{{.Names}}|{{.Image}}|{{.Status}}|{{.Mounts}}|{{.Ports}}"])
-        .output()
+    
+    // Two CSV samples one second apart; byte counters are cumulative
+    let output = Command::new("nettop")
+        .args(["-P", "-L", "2", "-s", "1", "-x", "-J", "bytes_in,bytes_out", "-p", &pid.to_string()])
+        .output_within()
         .ok()?;
     
     if !output.status.success() {
         return None;
     }
     
+    // Output format:
+    //   time,,bytes_in,bytes_out,
+    //   12:00:00.123456,node.1234,10240,20480,
     let text = String::from_utf8_lossy(&output.stdout);
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines.next()?.split(',').collect();
+    let in_idx = header.iter().position(|h| *h == "bytes_in")?;
+    let out_idx = header.iter().position(|h| *h == "bytes_out")?;
     
-    for line in text.lines() {
-        let parts: Vec<&str> = line.splitn(6, '|').collect();
-        if parts.len() < 6 {
-            continue;
-        }
-        
-        let ports_str = parts[5];
-        
-        // Check if this container exposes our port
-        if ports_str.contains(&format!(":{}", port)) || ports_str.contains(&format!("->{}/ ", port)) {
-            let volumes: Vec<String> = parts[4]
-                .split(',')
-                .filter(|s| !s.is_empty())
-                .map(|s| s.trim().to_string())
-                .collect();
-            
-            return Some(DockerInfo {
-                container_id: parts[0].to_string(),
-                container_name: parts[1].to_string(),
-                image: parts[2].to_string(),
-                status: parts[3].to_string(),
-                volumes,
-            });
+    let samples: Vec<(u64, u64)> = lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let bytes_in = fields.get(in_idx)?.trim().parse().ok()?;
+            let bytes_out = fields.get(out_idx)?.trim().parse().ok()?;
+            Some((bytes_in, bytes_out))
+        })
+        .collect();
+    
+    if samples.len() < 2 {
+        return None;
+    }
+    let (first, last) = (samples.first()?, samples.last()?);
+    
+    Some(Traffic {
+        bytes_in_per_sec: last.0.saturating_sub(first.0) as f64,
+        bytes_out_per_sec: last.1.saturating_sub(first.1) as f64,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn get_environment_variables(pid: u32) -> Vec<(String, String)> {
+    // Get important environment variables
+    let important_vars = [
+        "NODE_ENV", "PORT", "DATABASE_URL", "RAILS_ENV", "FLASK_ENV",
+        "DJANGO_SETTINGS_MODULE", "PYTHON_ENV", "GO_ENV", "RUST_ENV",
+        "PATH", "HOME", "USER", "PWD", "LANG"
+    ];
+    
+    // Only keep important vars to avoid clutter
+    read_process_environment(pid)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(key, _)| important_vars.contains(&key.as_str()))
+        .collect()
+}
+
+/// Read a process's environment with `sysctl(KERN_PROCARGS2)`. Unlike `ps eww`,
+/// entries are NUL-delimited, so values keep their spaces and `=` signs.
+#[cfg(target_os = "macos")]
+fn read_process_environment(pid: u32) -> Option<Vec<(String, String)>> {
+    // The buffer must be as large as the system's maximum argument size
+    let mut argmax: libc::c_int = 0;
+    let mut size = std::mem::size_of::<libc::c_int>();
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            2,
+            &mut argmax as *mut libc::c_int as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 || argmax <= 0 {
+        return None;
+    }
+
+    let mut buf = vec![0u8; argmax as usize];
+    let mut size = buf.len();
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid as libc::c_int];
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            3,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        return None;
+    }
+    buf.truncate(size);
+    parse_procargs2(&buf)
+}
+
+/// KERN_PROCARGS2 layout: argc as a native i32, the exec path, NUL padding,
+/// argc NUL-terminated arguments, then NUL-terminated `KEY=VALUE` entries
+#[cfg(target_os = "macos")]
+fn parse_procargs2(buf: &[u8]) -> Option<Vec<(String, String)>> {
+    let argc = i32::from_ne_bytes(buf.get(..4)?.try_into().ok()?) as usize;
+    let rest = &buf[4..];
+
+    // Skip the exec path and the padding after it
+    let path_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[path_end..];
+    let Some(args_start) = rest.iter().position(|&b| b != 0) else {
+        return Some(Vec::new());
+    };
+
+    let mut strings = rest[args_start..].split(|&b| b == 0);
+    for _ in 0..argc {
+        strings.next();
+    }
+
+    let env = strings
+        .take_while(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (key, value) = entry.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect();
+    Some(env)
+}
+
+/// lsof truncates command names (`com.docker.backe`), so the full exec path
+/// is checked as well as the name
+#[cfg(any(target_os = "macos", windows))]
+fn is_docker_process(process: Option<&str>, exec_path: Option<&str>) -> bool {
+    [process, exec_path]
+        .into_iter()
+        .flatten()
+        .any(|name| name.to_lowercase().contains("docker"))
+}
+
+#[cfg(target_os = "macos")]
+fn get_docker_info(port: u16, containers: &[DockerContainer]) -> Option<DockerInfo> {
+    // Check if a container exposes our port
+    let container = containers.iter().find(|c| c.publishes(port))?;
+    
+    let volumes: Vec<String> = container.mounts
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim().to_string())
+        .collect();
+    
+    let (networks, ip_address) = get_container_networks(&container.id);
+    
+    Some(DockerInfo {
+        container_id: container.id.clone(),
+        container_name: container.name.clone(),
+        image: container.image.clone(),
+        status: container.status.clone(),
+        volumes,
+        networks,
+        ip_address,
+    })
+}
+
+/// Networks a container is attached to, plus its first IP address
+#[cfg(target_os = "macos")]
+fn get_container_networks(container_id: &str) -> (Vec<String>, Option<String>) {
+    use std::process::Command;
+    let output = Command::new("docker")
+        .args([
+            "inspect",
+            "--format",
+            "{{range $name, $net := .NetworkSettings.Networks}}{{$name}}|{{$net.IPAddress}} {{end}}",
+            container_id,
+        ])
+        .output_within();
+    
+    let Ok(output) = output else {
+        return (Vec::new(), None);
+    };
+    
+    if !output.status.success() {
+        return (Vec::new(), None);
+    }
+    
+    // Output format: "bridge|172.17.0.2 myapp_default|172.18.0.3 "
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut networks = Vec::new();
+    let mut ip_address = None;
+    
+    for network in text.split_whitespace() {
+        let (name, ip) = network.split_once('|').unwrap_or((network, ""));
+        networks.push(name.to_string());
+        if ip_address.is_none() && !ip.is_empty() {
+            ip_address = Some(ip.to_string());
         }
     }
     
-    None
+    (networks, ip_address)
+}
+
+/// How many other listening ports the detailed view lists before summarising
+const MAX_OTHER_PORTS: usize = 10;
+
+/// How many connected peers the detailed view lists before summarising
+const MAX_PEERS: usize = 10;
+
+/// Longest command line (in characters) the detailed view shows without `--full`
+const MAX_COMMAND_CHARS: usize = 300;
+
+/// Longest environment value the detailed view shows without `--full`
+const MAX_ENV_VALUE_CHARS: usize = 100;
+
+/// Escape control characters (newlines, terminal escape sequences, ...) so a
+/// process can't garble the terminal through its command line or environment
+fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(char::is_control) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.chars()
+            .map(|c| if c.is_control() { c.escape_default().to_string() } else { c.to_string() })
+            .collect(),
+    )
 }
 
-fn print_detailed_port_info(info: &DetailedPortInfo, colors: bool) {
+/// Cut `text` to `max` characters, saying how much was left out
+fn shorten(text: &str, max: usize) -> Cow<'_, str> {
+    let len = text.chars().count();
+    if len <= max {
+        return Cow::Borrowed(text);
+    }
+    let kept: String = text.chars().take(max).collect();
+    Cow::Owned(format!("{}… ({} more characters, use --full)", kept, len - max))
+}
+
+fn print_detailed_port_info(
+    info: &DetailedPortInfo,
+    colors: bool,
+    thresholds: &Thresholds,
+    full: bool,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
     let header_color = if colors { "\x1b[1;36m" } else { "" };
     let label_color = if colors { "\x1b[1m" } else { "" };
     let section_color = if colors { "\x1b[1;34m" } else { "" }; // Blue for section titles
-    let kind_color = if colors {
-        match info.kind {
-            Kind::Dev => "\x1b[32m",
-            Kind::Database => "\x1b[36m",
-            Kind::Container => "\x1b[34m",
-            Kind::System => "\x1b[33m",
-            Kind::Unknown => "\x1b[31m",
-        }
-    } else {
-        ""
-    };
+    let kind_color = if colors { ansi_fg(get_kind_color(info.kind)) } else { Cow::Borrowed("") };
     let reset = if colors { "\x1b[0m" } else { "" };
     
     // Header
-    println!();
-    println!("{}╭─────────────────────────────────────────────────────────────────────╮{}", header_color, reset);
-    println!("{}│ Port {} - Process Details{}{}", header_color, info.port, " ".repeat(43 - info.port.to_string().len()), reset);
-    println!("{}╰─────────────────────────────────────────────────────────────────────╯{}", header_color, reset);
-    println!();
+    writeln!(out)?;
+    writeln!(out, "{}╭─────────────────────────────────────────────────────────────────────╮{}", header_color, reset)?;
+    writeln!(out, "{}│ Port {} - Process Details{}{}", header_color, info.port, " ".repeat(43 - info.port.to_string().len()), reset)?;
+    writeln!(out, "{}╰─────────────────────────────────────────────────────────────────────╯{}", header_color, reset)?;
+    writeln!(out)?;
     
     // Process Information
-    println!("{}PROCESS INFORMATION{}", section_color, reset);
-    println!("  {}Name:{} {}", label_color, reset, info.process_name);
-    println!("  {}PID:{} {}", label_color, reset, info.pid);
-    println!("  {}Category:{} {}{}{}", label_color, reset, kind_color, format_kind(info.kind), reset);
-    println!("  {}Command:{} {}", label_color, reset, info.command);
+    writeln!(out, "{}PROCESS INFORMATION{}", section_color, reset)?;
+    writeln!(out, "  {}Name:{} {}", label_color, reset, sanitize(&info.process_name))?;
+    writeln!(out, "  {}PID:{} {}", label_color, reset, info.pid)?;
+    if let Some(ref note) = info.note {
+        writeln!(out, "  {}Note:{} {}", label_color, reset, sanitize(note))?;
+    }
+    writeln!(out, "  {}Category:{} {}{}{}", label_color, reset, kind_color, format_kind(info.kind), reset)?;
+    writeln!(out, "  {}Command:{} {}", label_color, reset, sanitize(&info.exec))?;
+    if !info.args.is_empty() {
+        let args = info.args
+            .iter()
+            .map(|arg| if arg.contains(char::is_whitespace) { format!("'{}'", arg) } else { arg.clone() })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let args = sanitize(&args);
+        // Electron apps and the like pass thousands of characters of flags
+        let args = if full { args.into_owned() } else { shorten(&args, MAX_COMMAND_CHARS).into_owned() };
+        writeln!(out, "           {}", args)?;
+    }
+    if let Some(ref forward) = info.forwarded_by {
+        writeln!(out, "  {}Forwarded by:{} {}", label_color, reset, forward)?;
+    }
+    if let Some(ref kube) = info.kube_forward {
+        writeln!(out, "  {}Kubernetes:{} {}", label_color, reset, kube.describe())?;
+    }
+    if let Some(ref formula) = info.brew_service {
+        writeln!(out, "  {}Managed by:{} brew services ({})", label_color, reset, formula)?;
+        writeln!(out, "  {}Hint:{} stop it with `brew services stop {}`; killing the PID lets launchd restart it",
+            label_color, reset, formula)?;
+    }
+    if let Some(ref manager) = info.version_manager {
+        writeln!(out, "  {}Runtime:{} {}", label_color, reset, manager.describe())?;
+    }
     
     if let Some(ref dir) = info.working_dir {
-        println!("  {}Directory:{} {}", label_color, reset, dir);
+        writeln!(out, "  {}Directory:{} {}", label_color, reset, sanitize(dir))?;
     }
     
     if let Some(ref path) = info.exec_path {
-        println!("  {}Exec Path:{} {}", label_color, reset, path);
+        writeln!(out, "  {}Exec Path:{} {}", label_color, reset, sanitize(path))?;
     }
     
-    println!("  {}User:{} {} ({})", label_color, reset, info.user_name, info.uid);
-    println!("  {}Uptime:{} {} (started {})", label_color, reset, info.uptime, info.start_time);
-    println!();
+    if let Some(state) = info.state {
+        let state_color = if colors && state.is_abnormal() { "\x1b[31m" } else { "" };
+        writeln!(out, "  {}State:{} {}{}{}", label_color, reset, state_color, state.describe(), reset)?;
+    }
+    writeln!(out, "  {}User:{} {} ({})", label_color, reset, info.user_name, info.uid)?;
+    if is_privileged_port(info.port) {
+        let how = if info.uid == 0 {
+            "running as root"
+        } else {
+            "granted the right to bind it, or started as root and dropped privileges"
+        };
+        writeln!(out, "  {}Privileged:{} port {} is below 1024; {} is {}", label_color, reset, info.port, info.user_name, how)?;
+    }
+    writeln!(out, "  {}Uptime:{} {} (started {})", label_color, reset, info.uptime, info.start_time)?;
+    writeln!(out)?;
     
     // Process Tree
-    if !info.parent_chain.is_empty() || !info.children.is_empty() {
-        println!("{}PROCESS TREE{}", section_color, reset);
+    if !info.parent_chain.is_empty() || !info.children.is_empty() || info.session_leader.is_some() {
+        writeln!(out, "{}PROCESS TREE{}", section_color, reset)?;
         
         if !info.parent_chain.is_empty() {
             let chain_str = info.parent_chain
                 .iter()
-                .map(|(pid, name)| format!("{} ({})", name, pid))
+                .map(|p| format!("{} ({})", sanitize(&p.name), p.pid))
                 .collect::<Vec<_>>()
                 .join(" → ");
-            println!("  {}Parents:{} {} → {} ({})", 
-                label_color, reset, chain_str, info.process_name, info.pid);
+            writeln!(out, "  {}Parents:{} {} → {} ({})", 
+                label_color, reset, chain_str, sanitize(&info.process_name), info.pid)?;
+        } else if let Some(ppid) = info.parent_pid {
+            writeln!(out, "  {}Parents:{} None (parent PID {})", label_color, reset, ppid)?;
         } else {
-            println!("  {}Parents:{} None", label_color, reset);
+            writeln!(out, "  {}Parents:{} None", label_color, reset)?;
         }
         
         if !info.children.is_empty() {
             let children_str = info.children
                 .iter()
-                .map(|(pid, name)| format!("{} ({})", name, pid))
+                .map(|p| format!("{} ({})", sanitize(&p.name), p.pid))
                 .collect::<Vec<_>>()
                 .join(", ");
-            println!("  {}Children:{} {}", label_color, reset, children_str);
+            writeln!(out, "  {}Children:{} {}", label_color, reset, children_str)?;
         } else {
-            println!("  {}Children:{} None", label_color, reset);
+            writeln!(out, "  {}Children:{} None", label_color, reset)?;
+        }
+        
+        match info.session_leader {
+            Some(ref leader) if leader.pid == info.pid => {
+                writeln!(out, "  {}Session:{} leads its own session", label_color, reset)?;
+            }
+            Some(ref leader) => {
+                writeln!(out, "  {}Session:{} {} ({})", label_color, reset, sanitize(&leader.name), leader.pid)?;
+            }
+            None => {}
         }
-        println!();
+        writeln!(out)?;
     }
     
     // Resources
-    println!("{}RESOURCES{}", section_color, reset);
-    println!("  {}Memory:{} {} MB (RSS), {} MB (Virtual)", 
+    writeln!(out, "{}RESOURCES{}", section_color, reset)?;
+    // Red when over the configured thresholds
+    let alert = |over: bool| if colors && over { "\x1b[31m" } else { "" };
+    let memory_color = alert(info.memory_rss > thresholds.memory_mb * 1024);
+    let cpu_color = alert(info.cpu_usage > thresholds.cpu_percent);
+    writeln!(out, "  {}Memory:{} {}{} MB (RSS){}, {} MB (Virtual)", 
         label_color, reset,
+        memory_color,
         format_mb(info.memory_rss),
+        reset,
         format_mb(info.memory_virtual)
-    );
-    println!("  {}CPU:{} {}%", label_color, reset, format_float(info.cpu_usage, 1));
-    println!("  {}Threads:{} {}", label_color, reset, info.thread_count);
-    println!("  {}File Descriptors:{} {} open", label_color, reset, info.file_descriptors);
-    println!();
+    )?;
+    writeln!(out, "  {}CPU:{} {}{}%{}", label_color, reset, cpu_color, format_float(info.cpu_usage, 1), reset)?;
+    writeln!(out, "  {}Threads:{} {}", label_color, reset, info.thread_count)?;
+    let fds = &info.fd_breakdown;
+    let fd_parts: Vec<String> = [(fds.sockets, "socket"), (fds.files, "file"), (fds.pipes, "pipe")]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}{}", count, label, if *count == 1 { "" } else { "s" }))
+        .collect();
+    if fd_parts.is_empty() {
+        writeln!(out, "  {}File Descriptors:{} {} open", label_color, reset, info.file_descriptors)?;
+    } else {
+        writeln!(out, "  {}File Descriptors:{} {} open ({})", label_color, reset, info.file_descriptors, fd_parts.join(", "))?;
+    }
+    writeln!(out)?;
     
     // Network
-    println!("{}NETWORK{}", section_color, reset);
+    writeln!(out, "{}NETWORK{}", section_color, reset)?;
     
-    if !info.listen_addresses.is_empty() {
-        let binding_str = info.listen_addresses.join(", ");
-        let (ipv4, ipv6): (Vec<_>, Vec<_>) = info.listen_addresses
-            .iter()
-            .partition(|addr| !addr.contains('['));
-        
-        if !ipv4.is_empty() && !ipv6.is_empty() {
-            let ipv4_str: Vec<String> = ipv4.iter().map(|s| s.to_string()).collect();
-            let ipv6_str: Vec<String> = ipv6.iter().map(|s| s.to_string()).collect();
-            println!("  {}Binding:{} {} (IPv4) + {} (IPv6)", 
+    let listen_addresses: Vec<(IpFamily, String)> = info.listen_addresses
+        .iter()
+        .map(|(family, addr)| canonical_address(*family, addr))
+        .collect();
+    match bind_stack(&listen_addresses) {
+        Some(BindStack::DualStack) => {
+            let (ipv4, ipv6): (Vec<_>, Vec<_>) = listen_addresses
+                .iter()
+                .partition(|(family, _)| *family == IpFamily::V4);
+            let ipv4_str: Vec<&str> = ipv4.iter().map(|(_, addr)| addr.as_str()).collect();
+            let ipv6_str: Vec<&str> = ipv6.iter().map(|(_, addr)| addr.as_str()).collect();
+            writeln!(out, "  {}Binding:{} {} (IPv4) + {} (IPv6), dual-stack", 
                 label_color, reset,
                 ipv4_str.join(", "),
                 ipv6_str.join(", ")
-            );
-        } else {
-            println!("  {}Binding:{} {}", label_color, reset, binding_str);
+            )?;
         }
-    } else {
-        println!("  {}Binding:{} *:{}", label_color, reset, info.port);
+        Some(BindStack::Ipv6Only) => {
+            writeln!(out, "  {}Binding:{} {} (IPv6-only)", label_color, reset, join_addresses(&listen_addresses))?;
+            writeln!(out, "  {}Hint:{} IPv4 clients (e.g. curl 127.0.0.1:{}) can't connect; use ::1 / localhost", 
+                label_color, reset, info.port)?;
+        }
+        Some(BindStack::Ipv4Only) => {
+            writeln!(out, "  {}Binding:{} {} (IPv4-only)", label_color, reset, join_addresses(&listen_addresses))?;
+        }
+        None => {
+            writeln!(out, "  {}Binding:{} *:{}", label_color, reset, info.port)?;
+        }
+    }
+    
+    if let Some(ref addr) = info.reachable_at {
+        writeln!(out, "  {}Reachable at:{} {} (from other hosts on the network)", label_color, reset, addr)?;
+    }
+    writeln!(out, "  {}Protocol:{} TCP (LISTEN)", label_color, reset)?;
+    writeln!(
+        out,
+        "  {}Listening since:{} {} (process start; the socket's bind time isn't available)",
+        label_color, reset, info.listening_since
+    )?;
+    writeln!(out, "  {}Connections:{} {} active", label_color, reset, info.active_connections)?;
+    if !info.peers.is_empty() {
+        let mut peers_str = info.peers
+            .iter()
+            .take(MAX_PEERS)
+            .map(|peer| match peer.host {
+                Some(ref host) => format!("{} ({})", peer.address, sanitize(host)),
+                None => peer.address.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if info.peers.len() > MAX_PEERS {
+            peers_str.push_str(&format!(" …and {} more", info.peers.len() - MAX_PEERS));
+        }
+        writeln!(out, "  {}Peers:{} {}", label_color, reset, peers_str)?;
     }
     
-    println!("  {}Protocol:{} TCP (LISTEN)", label_color, reset);
-    println!("  {}Connections:{} {} active", label_color, reset, info.active_connections);
+    if let Some(queue) = info.listen_queue {
+        let full_color = alert(queue.nearly_full());
+        let full_note = if queue.nearly_full() { " (nearly full, new connections may be dropped)" } else { "" };
+        writeln!(out, "  {}Listen Queue:{} {}{}/{} queued{}{}, {} incomplete", 
+            label_color, reset, full_color, queue.queued, queue.max, full_note, reset, queue.incomplete)?;
+        if let Some(overflows) = queue.overflows.filter(|n| *n > 0) {
+            writeln!(out, "  {}Queue Overflows:{} {} connections dropped system-wide since boot", 
+                label_color, reset, overflows)?;
+        }
+    }
+    
+    if let Some(traffic) = info.traffic {
+        writeln!(out, "  {}Traffic:{} ~{} KB/s in, {} KB/s out (whole process)", 
+            label_color, reset,
+            format_float(traffic.bytes_in_per_sec / 1024.0, 1),
+            format_float(traffic.bytes_out_per_sec / 1024.0, 1)
+        )?;
+    }
     
     if !info.other_ports.is_empty() {
-        let ports_str = info.other_ports
+        let mut ports_str = info.other_ports
             .iter()
+            .take(MAX_OTHER_PORTS)
             .map(|p| p.to_string())
             .collect::<Vec<_>>()
             .join(", ");
-        println!("  {}Other Ports:{} Also listening on {}", label_color, reset, ports_str);
+        if info.other_ports.len() > MAX_OTHER_PORTS {
+            ports_str.push_str(&format!(" …and {} more", info.other_ports.len() - MAX_OTHER_PORTS));
+        }
+        writeln!(out, "  {}Other Ports:{} Also listening on {}", label_color, reset, ports_str)?;
     }
-    println!();
+    writeln!(out)?;
     
     // Environment Variables
     if !info.env_vars.is_empty() {
-        println!("{}ENVIRONMENT{}", section_color, reset);
+        writeln!(out, "{}ENVIRONMENT{}", section_color, reset)?;
         for (key, value) in info.env_vars.iter().take(10) {
-            // Truncate only PATH since it's typically very long
-            let display_value = if key == "PATH" && value.len() > 100 {
-                format!("{}...", &value[..97])
-            } else {
-                value.clone()
-            };
-            println!("  {}={}", key, display_value);
+            let value = sanitize(value);
+            let value = if full { value.into_owned() } else { shorten(&value, MAX_ENV_VALUE_CHARS).into_owned() };
+            writeln!(out, "  {}={}", sanitize(key), value)?;
         }
         if info.env_vars.len() > 10 {
-            println!("  ({} more environment variables)", info.env_vars.len() - 10);
+            writeln!(out, "  ({} more environment variables)", info.env_vars.len() - 10)?;
         }
-        println!();
+        writeln!(out)?;
     }
     
     // Docker Info
     if let Some(ref docker) = info.docker_info {
-        println!("{}CONTAINER INFORMATION{}", section_color, reset);
-        println!("  {}Container:{} {}", label_color, reset, docker.container_name);
-        println!("  {}ID:{} {}", label_color, reset, docker.container_id);
-        println!("  {}Image:{} {}", label_color, reset, docker.image);
-        println!("  {}Status:{} {}", label_color, reset, docker.status);
+        writeln!(out, "{}CONTAINER INFORMATION{}", section_color, reset)?;
+        writeln!(out, "  {}Container:{} {}", label_color, reset, docker.container_name)?;
+        writeln!(out, "  {}ID:{} {}", label_color, reset, docker.container_id)?;
+        writeln!(out, "  {}Image:{} {}", label_color, reset, docker.image)?;
+        writeln!(out, "  {}Status:{} {}", label_color, reset, docker.status)?;
+        
+        if !docker.networks.is_empty() {
+            writeln!(out, "  {}Networks:{} {}", label_color, reset, docker.networks.join(", "))?;
+        }
+        
+        if let Some(ref ip) = docker.ip_address {
+            writeln!(out, "  {}IP Address:{} {}", label_color, reset, ip)?;
+        }
         
         if !docker.volumes.is_empty() {
-            println!("  {}Volumes:{}", label_color, reset);
+            writeln!(out, "  {}Volumes:{}", label_color, reset)?;
             for vol in &docker.volumes {
-                println!("    - {}", vol);
+                writeln!(out, "    - {}", vol)?;
             }
         }
-        println!();
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Probe every entry concurrently, filling in `health`
+fn check_health(entries: &mut [PortEntry]) {
+    thread::scope(|scope| {
+        for entry in entries.iter_mut() {
+            scope.spawn(move || {
+                let http = entry.kind == Kind::Dev;
+                entry.health = Some(probe_port(probe_address(entry), http));
+            });
+        }
+    });
+}
+
+/// Pick an address to connect to for a listener, preferring its actual bind address
+fn probe_address(entry: &PortEntry) -> std::net::SocketAddr {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let mut fallback = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    for (family, addr) in &entry.addresses {
+        let host = address_host(addr);
+        // Wildcard binds accept loopback connections of the same family
+        match host.parse::<IpAddr>() {
+            Ok(ip) if !ip.is_unspecified() => return SocketAddr::new(ip, entry.port),
+            _ if *family == IpFamily::V6 => fallback = IpAddr::V6(Ipv6Addr::LOCALHOST),
+            _ => return SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), entry.port),
+        }
+    }
+    SocketAddr::new(fallback, entry.port)
+}
+
+/// TCP connect to the address; for web servers, also expect an answer to `HEAD /`
+fn probe_port(addr: std::net::SocketAddr, http: bool) -> Health {
+    use std::io::{ErrorKind, Read, Write};
+    use std::net::TcpStream;
+
+    const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+    let mut stream = match TcpStream::connect_timeout(&addr, PROBE_TIMEOUT) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => return Health::Timeout,
+        Err(_) => return Health::Refused,
+    };
+
+    if !http {
+        return Health::Ok;
+    }
+
+    // A listener that accepts but never answers is the zombie case we want to catch
+    let _ = stream.set_read_timeout(Some(PROBE_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(PROBE_TIMEOUT));
+    if stream.write_all(b"HEAD / HTTP/1.0\r\nHost: localhost\r\n\r\n").is_err() {
+        return Health::Refused;
+    }
+
+    let mut buf = [0u8; 16];
+    match stream.read(&mut buf) {
+        Ok(_) => Health::Ok,
+        Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => Health::Timeout,
+        Err(_) => Health::Refused,
+    }
+}
+
+fn format_health(health: Option<Health>) -> &'static str {
+    match health {
+        Some(Health::Ok) => "OK",
+        Some(Health::Refused) => "refused",
+        Some(Health::Timeout) => "timeout",
+        None => "-",
     }
 }
 
+/// Ports below 1024 can only be bound by root (or with a special grant)
+fn is_privileged_port(port: u16) -> bool {
+    port < 1024
+}
+
 fn format_mb(kb: u64) -> String {
     let mb = kb as f64 / 1024.0;
     format!("{:.1}", mb)
@@ -927,9 +5715,7 @@ fn format_float(val: f64, decimals: usize) -> String {
 }
 
 #[cfg(target_os = "macos")]
-fn discover_ports() -> Result<Vec<PortEntry>> {
-    use std::process::Command;
-
+fn discover_ports(config: &Config, docker: &DockerSnapshot) -> Result<Vec<PortEntry>> {
     // Use lsof -F for reliable port→PID mapping
     // -F: field output (parseable)
     // -n: no DNS lookups
@@ -939,24 +5725,20 @@ fn discover_ports() -> Result<Vec<PortEntry>> {
     // Output format:
     //   p<pid>
     //   c<command>
+    //   u<uid>
+    //   t<IPv4|IPv6>
     //   n<address>:<port>
-    let output = Command::new("lsof")
-        .args(["-nP", "-iTCP", "-sTCP:LISTEN", "-Fpcn"])
-        .output()
-        .context("failed to run lsof (is it installed?)")?;
-
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "lsof exited with status {}",
-            output.status
-        ));
-    }
+    let output = run_discovery_command("lsof", &["-nP", "-iTCP", "-sTCP:LISTEN", "-Fpcutn"], config.discovery)?;
 
     let text = String::from_utf8_lossy(&output.stdout);
     let mut entries = Vec::new();
+    let enrich = !config.discovery.no_enrich;
+    let command_lines = if enrich { get_all_command_lines() } else { Default::default() };
 
     let mut current_pid: Option<u32> = None;
     let mut current_cmd: Option<String> = None;
+    let mut current_uid: Option<u32> = None;
+    let mut current_family = IpFamily::V4;
 
     // Parse lsof -F output
     for line in text.lines() {
@@ -972,11 +5754,20 @@ fn discover_ports() -> Result<Vec<PortEntry>> {
                 // PID field
                 current_pid = value.parse::<u32>().ok();
                 current_cmd = None; // reset for new process
+                current_uid = None;
             }
             'c' => {
                 // Command name (from lsof, as fallback)
                 current_cmd = Some(value.to_string());
             }
+            'u' => {
+                // Owner of the process (and so of its sockets)
+                current_uid = value.parse::<u32>().ok();
+            }
+            't' => {
+                // Socket type, comes before the address of the same file
+                current_family = if value == "IPv6" { IpFamily::V6 } else { IpFamily::V4 };
+            }
             'n' => {
                 // Network address field (e.g., "*:3000" or "127.0.0.1:8080")
                 if let Some(pid) = current_pid {
@@ -985,9 +5776,10 @@ fn discover_ports() -> Result<Vec<PortEntry>> {
                         // Get process info from libproc
                         let process = get_process_name_libproc(pid)
                             .or_else(|| current_cmd.clone());
-                        let exec_path = get_exec_path_libproc(pid);
+                        let exec_path = if enrich { get_exec_path_libproc(pid) } else { None };
+                        let command = command_lines.get(&pid).map(String::as_str);
 
-                        let kind = classify(port, process.as_deref());
+                        let kind = classify(port, process.as_deref(), exec_path.as_deref(), command, value, config);
 
                         entries.push(PortEntry {
                             port,
@@ -995,6 +5787,19 @@ fn discover_ports() -> Result<Vec<PortEntry>> {
                             process,
                             exec_path,
                             kind,
+                            addresses: vec![(current_family, normalize_address(current_family, value))],
+                            protocol: Protocol::Tcp,
+                            uid: current_uid,
+                            project: false,
+                            health: None,
+                            connections: None,
+                            service: None,
+                            note: None,
+                            brew_service: None,
+                            cwd: None,
+                            memory_kb: None,
+                            cpu_percent: None,
+                            other_ports: Vec::new(),
                         });
                     }
                 }
@@ -1003,111 +5808,557 @@ fn discover_ports() -> Result<Vec<PortEntry>> {
         }
     }
 
-    // Deduplicate by (port, pid) - same process might have IPv4 + IPv6 listeners
-    let mut seen = std::collections::HashSet::new();
-    let unique_entries: Vec<_> = entries
-        .into_iter()
-        .filter(|e| {
-            if let Some(pid) = e.pid {
-                seen.insert((e.port, pid))
+    let mut result = merge_duplicate_entries(entries);
+
+    // Enrich container entries with Docker container names
+    if enrich {
+        enrich_docker_containers(&mut result, docker);
+        enrich_kubectl_forwards(&mut result);
+        enrich_brew_services(&mut result);
+    }
+
+    result.sort_by_key(|e| e.port);
+    Ok(result)
+}
+
+/// Merge entries sharing `(port, pid, protocol)` into a single row.
+///
+/// The same process usually shows up once per interface (e.g. IPv4 + IPv6),
+/// so the bind addresses are folded into the first entry instead of dropped.
+/// A TCP and a UDP socket on the same port stay separate rows.
+#[cfg(any(target_os = "macos", windows))]
+fn merge_duplicate_entries(entries: Vec<PortEntry>) -> Vec<PortEntry> {
+    let mut merged: Vec<PortEntry> = Vec::new();
+    let mut index_by_key: std::collections::HashMap<(u16, u32, Protocol), usize> = std::collections::HashMap::new();
+
+    for entry in entries {
+        let Some(pid) = entry.pid else {
+            merged.push(entry);
+            continue;
+        };
+
+        let key = (entry.port, pid, entry.protocol);
+        match index_by_key.get(&key) {
+            Some(&idx) => {
+                let existing = &mut merged[idx];
+                for addr in entry.addresses {
+                    if !existing.addresses.contains(&addr) {
+                        existing.addresses.push(addr);
+                    }
+                }
+                if existing.process.is_none() {
+                    existing.process = entry.process;
+                }
+            }
+            None => {
+                index_by_key.insert(key, merged.len());
+                merged.push(entry);
+            }
+        }
+    }
+
+    merged
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindStack {
+    Ipv4Only,
+    Ipv6Only,
+    DualStack,
+}
+
+/// Work out which address families a set of bind addresses covers
+fn bind_stack(addresses: &[(IpFamily, String)]) -> Option<BindStack> {
+    let has_v4 = addresses.iter().any(|(family, _)| *family == IpFamily::V4);
+    let has_v6 = addresses.iter().any(|(family, _)| *family == IpFamily::V6);
+    match (has_v4, has_v6) {
+        (true, true) => Some(BindStack::DualStack),
+        (false, true) => Some(BindStack::Ipv6Only),
+        (true, false) => Some(BindStack::Ipv4Only),
+        (false, false) => None,
+    }
+}
+
+/// Describe which address families a listener is bound on
+fn format_bind_scope(addresses: &[(IpFamily, String)]) -> &'static str {
+    match bind_stack(addresses) {
+        Some(BindStack::DualStack) => "IPv4+IPv6",
+        Some(BindStack::Ipv6Only) => "IPv6",
+        Some(BindStack::Ipv4Only) => "IPv4",
+        None => "-",
+    }
+}
+
+fn is_loopback(addr: &str) -> bool {
+    let host = address_host(addr).trim_start_matches('[').trim_end_matches(']');
+    host == "localhost"
+        || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Tidy an lsof `n` field for storage: drop the " (LISTEN)" state annotation some
+/// lsof versions append, then put it in canonical form
+#[cfg(target_os = "macos")]
+fn normalize_address(family: IpFamily, addr: &str) -> String {
+    let addr = match addr.rfind(" (") {
+        Some(pos) if addr.ends_with(')') => &addr[..pos],
+        _ => addr,
+    };
+    canonical_address(family, addr).1
+}
+
+/// An address in canonical `ip:port` form, IPv6 in brackets, with the family
+/// it actually belongs to: "*:3000" -> "0.0.0.0:3000" or "[::]:3000" (per
+/// `family`), ":::5432" -> "[::]:5432". Anything that doesn't parse, like a
+/// zone-scoped IPv6 address, is kept as it is.
+fn canonical_address(family: IpFamily, addr: &str) -> (IpFamily, String) {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let parsed = addr.parse::<SocketAddr>().ok().or_else(|| {
+        let (host, port) = addr.rsplit_once(':')?;
+        let port = port.parse().ok()?;
+        let ip = match host {
+            "*" if family == IpFamily::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            "*" => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            // Unbracketed IPv6, as some tools print it
+            _ => IpAddr::V6(host.parse().ok()?),
+        };
+        Some(SocketAddr::new(ip, port))
+    });
+    match parsed {
+        Some(sock) if sock.is_ipv6() => (IpFamily::V6, sock.to_string()),
+        Some(sock) => (IpFamily::V4, sock.to_string()),
+        None => (family, addr.to_string()),
+    }
+}
+
+/// The host part of an lsof address: "[::1]:5432" -> "::1", "*:3000" -> "*"
+fn address_host(addr: &str) -> &str {
+    let host = addr.rsplit_once(':').map_or("", |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+fn join_addresses(addresses: &[(IpFamily, String)]) -> String {
+    addresses
+        .iter()
+        .map(|(_, addr)| addr.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A running container, as listed by `docker ps`
+#[derive(Debug, Clone)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))] // only the macOS detailed view reads status/mounts
+struct DockerContainer {
+    id: String,
+    name: String,
+    image: String,
+    status: String,
+    mounts: String,
+    ports: Vec<DockerPortMapping>,
+}
+
+impl DockerContainer {
+    /// Whether the container publishes `port` on the host over TCP
+    fn publishes(&self, port: u16) -> bool {
+        self.ports.iter().any(|m| {
+            m.protocol == Protocol::Tcp && (m.host_start..=m.host_end).contains(&port)
+        })
+    }
+}
+
+/// A container that has exited, with the host ports it used to publish
+#[cfg(any(target_os = "macos", windows))]
+#[derive(Debug, Clone)]
+struct ExitedContainer {
+    name: String,
+    host_ports: Vec<u16>,
+}
+
+/// `docker ps`, run lazily and at most once per snapshot
+struct DockerSnapshot {
+    enabled: bool,
+    containers: std::cell::OnceCell<Vec<DockerContainer>>,
+    #[cfg(any(target_os = "macos", windows))]
+    exited: std::cell::OnceCell<Vec<ExitedContainer>>,
+}
+
+impl DockerSnapshot {
+    /// A disabled snapshot (`--no-docker`) never runs docker and has no containers
+    fn new(enabled: bool) -> Self {
+        DockerSnapshot {
+            enabled,
+            containers: std::cell::OnceCell::new(),
+            #[cfg(any(target_os = "macos", windows))]
+            exited: std::cell::OnceCell::new(),
+        }
+    }
+
+    fn containers(&self) -> &[DockerContainer] {
+        self.containers.get_or_init(|| {
+            // Don't spawn a command that can only fail
+            if self.enabled && docker_on_path() {
+                list_docker_containers()
             } else {
-                true
+                Vec::new()
+            }
+        })
+    }
+
+    /// Exited containers, only looked up when a listener has no running container
+    #[cfg(any(target_os = "macos", windows))]
+    fn exited_containers(&self) -> &[ExitedContainer] {
+        self.exited.get_or_init(|| {
+            if self.enabled && docker_on_path() {
+                list_exited_containers()
+            } else {
+                Vec::new()
+            }
+        })
+    }
+}
+
+fn docker_on_path() -> bool {
+    find_on_path("docker").is_some()
+}
+
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let binary = if cfg!(windows) { format!("{name}.exe") } else { name.to_string() };
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(&binary)).find(|candidate| candidate.is_file())
+}
+
+/// Running containers; empty when Docker isn't installed or running
+fn list_docker_containers() -> Vec<DockerContainer> {
+    use std::process::Command;
+
+    // Format: <container_id>|<name>|<image>|<status>|<mounts>|<ports>
+    let output = Command::new("docker")
+        .args(["ps", "--format", "{{.ID}}|{{.Names}}|{{.Image}}|{{.Status}}|{{.Mounts}}|{{.Ports}}"])
+        .output_within();
+
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(6, '|').collect();
+            if parts.len() < 6 {
+                return None;
             }
+            Some(DockerContainer {
+                id: parts[0].to_string(),
+                name: parts[1].to_string(),
+                image: parts[2].to_string(),
+                status: parts[3].to_string(),
+                mounts: parts[4].to_string(),
+                ports: parse_docker_port_mappings(parts[5]),
+            })
         })
+        .collect()
+}
+
+/// Exited containers and the host ports they published. `docker ps` shows no
+/// ports for stopped containers, so they come from `docker inspect`.
+#[cfg(any(target_os = "macos", windows))]
+fn list_exited_containers() -> Vec<ExitedContainer> {
+    use std::process::Command;
+
+    let Ok(output) = Command::new("docker")
+        .args(["ps", "-aq", "--filter", "status=exited"])
+        .output_within()
+    else {
+        return Vec::new();
+    };
+    let ids: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .map(str::to_string)
         .collect();
+    if !output.status.success() || ids.is_empty() {
+        return Vec::new();
+    }
 
-    let mut result = unique_entries;
+    // Format: /<name>|{"5432/tcp":[{"HostIp":"","HostPort":"5432"}]}
+    let Ok(output) = Command::new("docker")
+        .args(["inspect", "--format", "{{.Name}}|{{json .HostConfig.PortBindings}}"])
+        .args(&ids)
+        .output_within()
+    else {
+        return Vec::new();
+    };
 
-    // Enrich container entries with Docker container names
-    enrich_docker_containers(&mut result);
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, bindings) = line.split_once('|')?;
+            let bindings: serde_json::Value = serde_json::from_str(bindings).ok()?;
+            let host_ports: Vec<u16> = bindings
+                .as_object()?
+                .iter()
+                // Only TCP mappings can belong to a TCP listener
+                .filter(|(container_port, _)| container_port.ends_with("/tcp"))
+                .filter_map(|(_, hosts)| hosts.as_array())
+                .flatten()
+                .filter_map(|host| host.get("HostPort")?.as_str()?.parse().ok())
+                .collect();
+            if host_ports.is_empty() {
+                return None;
+            }
+            Some(ExitedContainer { name: name.trim_start_matches('/').to_string(), host_ports })
+        })
+        .collect()
+}
 
-    result.sort_by_key(|e| e.port);
-    Ok(result)
+/// Map each published host TCP port to its container's (name, image)
+#[cfg(any(target_os = "macos", windows))]
+fn container_port_map(containers: &[DockerContainer]) -> std::collections::HashMap<u16, (String, String)> {
+    let mut port_to_container = std::collections::HashMap::new();
+    for container in containers {
+        // Only TCP mappings can belong to a TCP listener
+        for mapping in &container.ports {
+            if mapping.protocol != Protocol::Tcp {
+                continue;
+            }
+            // Expand ranges (0.0.0.0:8000-8005->8000-8005/tcp) so every
+            // listener in them gets the container name
+            for port in mapping.host_start..=mapping.host_end {
+                port_to_container.insert(port, (container.name.clone(), container.image.clone()));
+            }
+        }
+    }
+    port_to_container
+}
+
+#[cfg(any(target_os = "macos", windows))]
+fn enrich_docker_containers(entries: &mut [PortEntry], docker: &DockerSnapshot) {
+    // Only look Docker up when there is something to enrich
+    if !entries.iter().any(|e| e.kind == Kind::Container) {
+        return;
+    }
+
+    let port_to_container = container_port_map(docker.containers());
+
+    // Enrich entries that are Docker processes
+    for entry in entries.iter_mut() {
+        if entry.kind == Kind::Container
+            && is_docker_process(entry.process.as_deref(), entry.exec_path.as_deref())
+        {
+            // Look up the container name for this port
+            if let Some((container_name, image)) = port_to_container.get(&entry.port) {
+                // Try to get a friendly name from the image
+                let friendly_name = get_friendly_container_name(container_name, image);
+                entry.process = Some(friendly_name);
+            } else if let Some(exited) = docker.exited_containers().iter().find(|c| c.host_ports.contains(&entry.port)) {
+                // Docker's port proxy sometimes outlives a crashed container
+                entry.process = Some(format!("stale mapping (container {} exited)", exited.name));
+            } else {
+                // No Docker container found, try to guess based on port
+                if let Some(service_name) = guess_service_by_port(entry.port) {
+                    entry.process = Some(format!("{} (container)", service_name));
+                }
+            }
+        }
+    }
+}
+
+/// Where Homebrew installs on Apple Silicon, Intel Macs and Linux
+#[cfg(target_os = "macos")]
+const BREW_PREFIXES: [&str; 3] = ["/opt/homebrew/", "/usr/local/", "/home/linuxbrew/.linuxbrew/"];
+
+/// The formula running `exec_path` under `brew services`, e.g. "postgresql@16" for
+/// /opt/homebrew/Cellar/postgresql@16/16.1/bin/postgres. A Homebrew binary counts
+/// only when `brew services` has installed its launchd plist; one started by hand
+/// isn't managed by anything.
+#[cfg(target_os = "macos")]
+fn brew_service_name(exec_path: &str) -> Option<String> {
+    let rest = BREW_PREFIXES.iter().find_map(|prefix| exec_path.strip_prefix(prefix))?;
+    let rest = rest.strip_prefix("Cellar/").or_else(|| rest.strip_prefix("opt/"))?;
+    let formula = rest.split('/').next().filter(|f| !f.is_empty())?;
+
+    // `brew services start` installs a LaunchAgent, `sudo brew services start` a LaunchDaemon
+    let plist = format!("homebrew.mxcl.{formula}.plist");
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let agent = home.map(|h| h.join("Library/LaunchAgents").join(&plist));
+    let daemon = Path::new("/Library/LaunchDaemons").join(&plist);
+    if agent.is_some_and(|p| p.exists()) || daemon.exists() {
+        return Some(formula.to_string());
+    }
+    None
+}
+
+/// The version manager behind an executable in its install or shim directory,
+/// e.g. ~/.nvm/versions/node/v20.11.0/bin/node or
+/// ~/.local/share/mise/installs/python/3.12.1/bin/python3
+#[cfg(target_os = "macos")]
+fn version_manager_from_path(exec_path: &str) -> Option<VersionManager> {
+    let parts: Vec<&str> = exec_path.split('/').filter(|p| !p.is_empty()).collect();
+    let found = |manager: &str, runtime: &str, version: Option<&str>| VersionManager {
+        manager: manager.to_string(),
+        // asdf's node plugin is called nodejs
+        runtime: if runtime == "nodejs" { "node" } else { runtime }.to_string(),
+        version: version.map(str::to_string),
+    };
+    for (i, part) in parts.iter().enumerate() {
+        let rest = &parts[i + 1..];
+        let manager = match *part {
+            ".asdf" => "asdf",
+            "mise" | "rtx" => "mise",
+            ".rbenv" => "rbenv",
+            ".nvm" => "nvm",
+            _ => continue,
+        };
+        match (manager, rest) {
+            ("asdf" | "mise", ["installs", tool, version, ..]) => return Some(found(manager, tool, Some(version))),
+            ("rbenv", ["versions", version, ..]) => return Some(found(manager, "ruby", Some(version))),
+            ("nvm", ["versions", "node", version, ..]) => return Some(found(manager, "node", Some(version))),
+            // A shim that didn't exec the real binary; the runtime is its name
+            ("asdf" | "mise" | "rbenv", ["shims", bin]) => return Some(found(manager, bin, None)),
+            _ => {}
+        }
+    }
+    None
 }
 
+/// A version manager among the process's parents, e.g. `mise exec -- node`
 #[cfg(target_os = "macos")]
-fn enrich_docker_containers(entries: &mut [PortEntry]) {
-    use std::process::Command;
-
-    // Query Docker for all running containers with their ports, names, and images
-    // Format: <container_id>|<name>|<image>|<ports>
-    let output = Command::new("docker")
-        .args(["ps", "--format", "{{.ID}}|{{.Names}}|{{.Image}}|{{.Ports}}"])
-        .output();
+fn version_manager_from_parents(parents: &[ProcessRef], process_name: &str) -> Option<VersionManager> {
+    let manager = parents
+        .iter()
+        .rev()
+        .find_map(|p| ["asdf", "mise", "rtx", "rbenv", "nvm"].into_iter().find(|m| p.name == *m))?;
+    Some(VersionManager {
+        manager: if manager == "rtx" { "mise" } else { manager }.to_string(),
+        runtime: process_name.to_string(),
+        version: None,
+    })
+}
 
-    let Ok(output) = output else {
-        // Docker not available or not running
-        return;
-    };
+/// Mark listeners started by `brew services`
+#[cfg(target_os = "macos")]
+fn enrich_brew_services(entries: &mut [PortEntry]) {
+    for entry in entries.iter_mut() {
+        entry.brew_service = entry.exec_path.as_deref().and_then(brew_service_name);
+    }
+}
 
-    if !output.status.success() {
-        return;
+/// Replace bare `kubectl` rows with the resource they forward to
+#[cfg(target_os = "macos")]
+fn enrich_kubectl_forwards(entries: &mut [PortEntry]) {
+    for entry in entries.iter_mut() {
+        let is_kubectl = entry.process.as_deref().is_some_and(|p| p.contains("kubectl"));
+        let Some(pid) = entry.pid.filter(|_| is_kubectl) else {
+            continue;
+        };
+        if let Some(forward) = get_command_line(pid).and_then(|c| parse_kubectl_port_forward(&c)) {
+            entry.process = Some(format!("{} (kubectl)", forward.describe()));
+        }
     }
+}
 
-    let text = String::from_utf8_lossy(&output.stdout);
+/// Parse `kubectl [flags] port-forward [flags] TYPE/NAME PORTS...`.
+///
+/// The namespace may be given anywhere as `-n NS`, `-nNS`, `-n=NS`, `--namespace NS`
+/// or `--namespace=NS`; the target is the first positional after `port-forward`.
+#[cfg(target_os = "macos")]
+fn parse_kubectl_port_forward(command: &str) -> Option<KubeForward> {
+    // kubectl flags that take a separate value, which must not be mistaken for the target
+    const VALUE_FLAGS: [&str; 8] = [
+        "--address", "--pod-running-timeout", "--context", "--cluster",
+        "--kubeconfig", "--user", "--server", "-s",
+    ];
 
-    // Build a map of port -> (container name, image)
-    let mut port_to_container: std::collections::HashMap<u16, (String, String)> = std::collections::HashMap::new();
+    let mut args = command.split_whitespace().skip(1);
+    let mut namespace = None;
+    let mut target = None;
+    let mut seen_subcommand = false;
 
-    for line in text.lines() {
-        if line.is_empty() {
+    while let Some(arg) = args.next() {
+        if arg == "-n" || arg == "--namespace" {
+            namespace = args.next().map(str::to_string);
+        } else if let Some(ns) = arg.strip_prefix("--namespace=") {
+            namespace = Some(ns.to_string());
+        } else if let Some(ns) = arg.strip_prefix("-n").map(|ns| ns.trim_start_matches('=')).filter(|ns| !ns.is_empty()) {
+            namespace = Some(ns.to_string());
+        } else if VALUE_FLAGS.contains(&arg) {
+            args.next();
+        } else if arg.starts_with('-') {
             continue;
+        } else if !seen_subcommand {
+            seen_subcommand = arg == "port-forward";
+            if !seen_subcommand {
+                return None;
+            }
+        } else if target.is_none() {
+            target = Some(arg.to_string());
         }
+    }
 
-        // Parse the format: container_id|name|image|ports
-        let parts: Vec<&str> = line.splitn(4, '|').collect();
-        if parts.len() < 4 {
-            continue;
-        }
+    target.map(|target| KubeForward { target, namespace })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
 
-        let container_name = parts[1];
-        let image = parts[2];
-        let ports_str = parts[3];
+/// A published port range from `docker ps`, e.g. `0.0.0.0:5000-5005->5000-5005/tcp`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DockerPortMapping {
+    host_start: u16,
+    host_end: u16,
+    protocol: Protocol,
+}
 
-        // Parse ports from Docker format: "0.0.0.0:8080->80/tcp, 0.0.0.0:8443->443/tcp"
-        // We want to extract the host port (e.g., 8080, 8443)
-        for port_mapping in ports_str.split(',') {
-            let port_mapping = port_mapping.trim();
+/// Parse the `{{.Ports}}` column of `docker ps` into host port mappings.
+///
+/// Handles the forms Docker emits:
+///   0.0.0.0:8080->80/tcp         (IPv4 host IP)
+///   [::]:8080->80/tcp, :::8080->80/tcp  (IPv6 host IP)
+///   8080->80/tcp                 (no host IP)
+///   0.0.0.0:5000-5005->5000-5005/udp  (ranges)
+/// Exposed-but-unpublished ports like `80/tcp` have no host side and are skipped.
+fn parse_docker_port_mappings(ports_str: &str) -> Vec<DockerPortMapping> {
+    let mut mappings = Vec::new();
 
-            // Look for patterns like "0.0.0.0:6379->6379/tcp" or ":::6379->6379/tcp"
-            if let Some(arrow_pos) = port_mapping.find("->") {
-                let before_arrow = &port_mapping[..arrow_pos];
+    for port_mapping in ports_str.split(',') {
+        let port_mapping = port_mapping.trim();
 
-                // Extract the host port (after the last colon before ->)
-                if let Some(colon_pos) = before_arrow.rfind(':') {
-                    let port_str = &before_arrow[colon_pos + 1..];
-                    if let Ok(port) = port_str.parse::<u16>() {
-                        port_to_container.insert(port, (container_name.to_string(), image.to_string()));
-                    }
-                }
-            }
-        }
-    }
+        let Some((host, container)) = port_mapping.split_once("->") else {
+            continue;
+        };
 
-    // Enrich entries that are Docker processes
-    for entry in entries.iter_mut() {
-        if entry.kind == Kind::Container {
-            if let Some(process) = &entry.process {
-                // Check if it's a Docker process
-                if process.to_lowercase().contains("docker") {
-                    // Look up the container name for this port
-                    if let Some((container_name, image)) = port_to_container.get(&entry.port) {
-                        // Try to get a friendly name from the image
-                        let friendly_name = get_friendly_container_name(container_name, image);
-                        entry.process = Some(friendly_name);
-                    } else {
-                        // No Docker container found, try to guess based on port
-                        if let Some(service_name) = guess_service_by_port(entry.port) {
-                            entry.process = Some(format!("{} (container)", service_name));
-                        }
-                    }
-                }
-            }
+        let protocol = match container.rsplit_once('/').map(|(_, proto)| proto) {
+            Some("udp") => Protocol::Udp,
+            Some("tcp") | None => Protocol::Tcp,
+            Some(_) => continue, // sctp and friends
+        };
+
+        // The host port is whatever follows the last colon (if there is a host IP at all)
+        let host_ports = host.rsplit_once(':').map_or(host, |(_, ports)| ports);
+        let (start, end) = match host_ports.split_once('-') {
+            Some((start, end)) => (start.parse::<u16>(), end.parse::<u16>()),
+            None => (host_ports.parse::<u16>(), host_ports.parse::<u16>()),
+        };
+
+        if let (Ok(host_start), Ok(host_end)) = (start, end)
+            && host_start <= host_end
+        {
+            mappings.push(DockerPortMapping { host_start, host_end, protocol });
         }
     }
+
+    mappings
 }
 
 /// Get a friendly container name from the container name and image
+#[cfg(any(target_os = "macos", windows))]
 fn get_friendly_container_name(container_name: &str, image: &str) -> String {
     // Extract the base image name (e.g., "redis" from "redis:7-alpine")
     let image_base = image
@@ -1115,7 +6366,7 @@ fn get_friendly_container_name(container_name: &str, image: &str) -> String {
         .next()
         .unwrap_or(image)
         .split('/')
-        .last()
+        .next_back()
         .unwrap_or(image);
 
     // Use the image base name if it's more descriptive than the container name
@@ -1129,6 +6380,7 @@ fn get_friendly_container_name(container_name: &str, image: &str) -> String {
 }
 
 /// Check if a name is generic/auto-generated
+#[cfg(any(target_os = "macos", windows))]
 fn is_generic_name(name: &str) -> bool {
     // Docker auto-generated names or hash-like names
     name.len() > 20 || name.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
@@ -1172,7 +6424,108 @@ fn guess_service_by_port(port: u16) -> Option<&'static str> {
     }
 }
 
-#[cfg(target_os = "macos")]
+/// Parse a port argument, accepting well-known service names as well as numbers
+fn parse_port(value: &str) -> Result<u16, String> {
+    if let Ok(port) = value.parse::<u16>() {
+        return Ok(port);
+    }
+
+    let name = value.to_lowercase();
+
+    // Reverse of guess_service_by_port (cheap enough to just scan every port)
+    let known: Vec<(u16, &str)> = (1..=u16::MAX)
+        .filter_map(|port| guess_service_by_port(port).map(|service| (port, service)))
+        .collect();
+
+    if let Some((port, _)) = known.iter().find(|(_, service)| *service == name) {
+        return Ok(*port);
+    }
+
+    if let Some(port) = lookup_etc_services(&name) {
+        return Ok(port);
+    }
+
+    // Allow shorthands like "postgres" for "postgresql"
+    let candidates: Vec<&(u16, &str)> = known
+        .iter()
+        .filter(|(_, service)| service.starts_with(&name))
+        .collect();
+
+    match candidates.as_slice() {
+        [(port, _)] => Ok(*port),
+        [] => Err(format!("unknown port or service name '{value}'")),
+        many => Err(format!(
+            "'{value}' is ambiguous: {}",
+            many.iter()
+                .map(|(port, service)| format!("{service} ({port})"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Parse `START-END` (or a single port) for `porty scan`; either end may be a service name
+fn parse_port_range(value: &str) -> Result<(u16, u16), String> {
+    // Service names may contain dashes themselves (e.g. http-alt)
+    if let Ok(port) = parse_port(value) {
+        return Ok((port, port));
+    }
+    let Some((start, end)) = value.split_once('-') else {
+        return parse_port(value).map(|port| (port, port));
+    };
+    let (start, end) = (parse_port(start.trim())?, parse_port(end.trim())?);
+    if start > end {
+        return Err(format!("invalid range '{value}': {start} is greater than {end}"));
+    }
+    Ok((start, end))
+}
+
+/// A TCP entry of the system services file
+struct ServiceEntry {
+    name: String,
+    port: u16,
+    aliases: Vec<String>,
+}
+
+/// TCP entries of /etc/services, read once; empty when the file is missing
+fn etc_services() -> &'static [ServiceEntry] {
+    static SERVICES: std::sync::OnceLock<Vec<ServiceEntry>> = std::sync::OnceLock::new();
+    SERVICES.get_or_init(|| {
+        let path = if cfg!(windows) { r"C:\Windows\System32\drivers\etc\services" } else { "/etc/services" };
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return Vec::new();
+        };
+
+        // Lines look like: "postgresql      5432/tcp    postgres  # PostgreSQL Database"
+        text.lines()
+            .filter_map(|line| {
+                let line = line.split('#').next().unwrap_or("");
+                let mut fields = line.split_whitespace();
+                let name = fields.next()?.to_string();
+                let port = fields.next()?.strip_suffix("/tcp")?.parse().ok()?;
+                let aliases = fields.map(str::to_string).collect();
+                Some(ServiceEntry { name, port, aliases })
+            })
+            .collect()
+    })
+}
+
+/// Find a TCP service by name or alias in /etc/services
+fn lookup_etc_services(name: &str) -> Option<u16> {
+    etc_services()
+        .iter()
+        .find(|s| s.name.eq_ignore_ascii_case(name) || s.aliases.iter().any(|a| a.eq_ignore_ascii_case(name)))
+        .map(|s| s.port)
+}
+
+/// Name of the service usually on `port`: porty's own list for the common
+/// dev and database ports, then /etc/services for the long tail
+fn service_name(port: u16) -> Option<&'static str> {
+    guess_service_by_port(port)
+        .or_else(|| etc_services().iter().find(|s| s.port == port).map(|s| s.name.as_str()))
+}
+
+#[cfg(any(target_os = "macos", windows))]
 fn extract_port(addr: &str) -> Option<u16> {
     // Handle formats like:
     // *:3000
@@ -1234,56 +6587,313 @@ fn get_exec_path_libproc(pid: u32) -> Option<String> {
     std::str::from_utf8(bytes).ok().map(|s| s.to_string())
 }
 
-#[cfg(not(target_os = "macos"))]
-fn discover_ports() -> Result<Vec<PortEntry>> {
-    Err(anyhow::anyhow!("This tool only supports macOS"))
+#[cfg(windows)]
+fn discover_ports(config: &Config, docker: &DockerSnapshot) -> Result<Vec<PortEntry>> {
+    // netstat -ano lists every socket with its owning PID:
+    //   Proto  Local Address    Foreign Address  State        PID
+    //   TCP    0.0.0.0:3000     0.0.0.0:0        LISTENING    1234
+    //   TCP    [::]:3000        [::]:0           LISTENING    1234
+    let output = run_discovery_command("netstat", &["-ano"], config.discovery)?;
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let names = get_process_names_windows();
+    let mut entries = Vec::new();
+
+    for line in text.lines() {
+        let Some((port, pid, local)) = netstat_listener(line) else {
+            continue;
+        };
+
+        let (family, local) = canonical_address(IpFamily::V4, local);
+        let process = names.get(&pid).cloned();
+        let kind = classify(port, process.as_deref(), None, None, &local, config);
+
+        entries.push(PortEntry {
+            port,
+            pid: Some(pid),
+            process,
+            exec_path: None,
+            kind,
+            addresses: vec![(family, local)],
+            protocol: Protocol::Tcp,
+            uid: None,
+            project: false,
+            health: None,
+            connections: None,
+            service: None,
+            note: None,
+            brew_service: None,
+            cwd: None,
+            memory_kb: None,
+            cpu_percent: None,
+            other_ports: Vec::new(),
+        });
+    }
+
+    let mut result = merge_duplicate_entries(entries);
+
+    // Docker Desktop forwards published ports the same way as on macOS
+    if !config.discovery.no_enrich {
+        enrich_docker_containers(&mut result, docker);
+    }
+
+    result.sort_by_key(|e| e.port);
+    Ok(result)
 }
 
-fn print_table(entries: Vec<PortEntry>, verbose: bool, colors: bool) {
-    if entries.is_empty() {
-        println!("No ports found.");
-        return;
+/// Port, PID and local address of a `netstat -ano` line for a listening TCP
+/// socket. The state column is translated (`ABHÖREN` on a German Windows), so
+/// listeners are told apart by their foreign address, whose port is 0 only then.
+#[cfg(windows)]
+fn netstat_listener(line: &str) -> Option<(u16, u32, &str)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 5 || fields[0] != "TCP" || extract_port(fields[2]) != Some(0) {
+        return None;
     }
+    let local = fields[1];
+    Some((extract_port(local)?, fields[4].parse().ok()?, local))
+}
 
-    let mut table = Table::new();
-    table.load_preset(UTF8_FULL);
-    table.apply_modifier(UTF8_ROUND_CORNERS);
-    table.set_content_arrangement(ContentArrangement::Dynamic);
-    table.set_width(100);
+/// Map PIDs to image names using a single `tasklist` call
+#[cfg(windows)]
+fn get_process_names_windows() -> std::collections::HashMap<u32, String> {
+    use std::process::Command;
+
+    let mut names = std::collections::HashMap::new();
+
+    // CSV rows look like: "node.exe","1234","Console","1","45,120 K"
+    let Ok(output) = Command::new("tasklist").args(["/FO", "CSV", "/NH"]).output_within() else {
+        return names;
+    };
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    for line in text.lines() {
+        let mut fields = line.split("\",\"").map(|f| f.trim_matches('"'));
+        let (Some(name), Some(pid)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(pid) = pid.parse::<u32>() {
+            let name = name.strip_suffix(".exe").unwrap_or(name);
+            names.insert(pid, name.to_string());
+        }
+    }
+
+    names
+}
+
+#[cfg(windows)]
+fn windows_pid_alive(pid: u32) -> bool {
+    use std::process::Command;
+
+    let Ok(output) = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output_within()
+    else {
+        return false;
+    };
+
+    String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid))
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn discover_ports(_config: &Config, _docker: &DockerSnapshot) -> Result<Vec<PortEntry>> {
+    Err(anyhow::anyhow!("This tool only supports macOS and Windows"))
+}
 
-    if verbose {
-        table.set_header(vec!["PORT", "PROCESS", "CATEGORY", "PID", "EXEC PATH"]);
+/// An empty table with the requested style and width
+fn new_table(opts: &TableOptions) -> Table {
+    let mut table = Table::new();
+    match opts.style {
+        TableStyle::Rounded => {
+            table.load_preset(UTF8_FULL);
+            table.apply_modifier(UTF8_ROUND_CORNERS);
+        }
+        TableStyle::Ascii => {
+            table.load_preset(ASCII_FULL);
+        }
+        TableStyle::Plain => {
+            table.load_preset(NOTHING);
+        }
+        TableStyle::Markdown => {
+            table.load_preset(ASCII_MARKDOWN);
+        }
+    }
+    if opts.wide {
+        table.set_content_arrangement(ContentArrangement::Disabled);
     } else {
-        table.set_header(vec!["PORT", "PROCESS", "CATEGORY", "PID"]);
+        table.set_content_arrangement(ContentArrangement::Dynamic);
+        if let Some(width) = opts.width {
+            table.set_width(width);
+        } else if table.width().is_none() {
+            // Terminal width can't be detected (e.g. piped output)
+            table.set_width(100);
+        }
+    }
+
+    table
+}
+
+fn print_table(entries: Vec<PortEntry>, opts: &TableOptions, out: &mut dyn Write) -> std::io::Result<()> {
+    if entries.is_empty() {
+        writeln!(out, "No ports found.")?;
+        return Ok(());
+    }
+
+    let mut table = new_table(opts);
+
+    let columns = opts.columns.clone().unwrap_or_else(|| default_columns(&entries, opts));
+    let shared = shared_ports(&entries);
+    let has_project = entries.iter().any(|e| e.project);
+    let summary = summarize(&entries);
+
+    table.set_header(columns.iter().map(|c| c.header()).collect::<Vec<_>>());
+
+    for e in &entries {
+        let row: Vec<Cell> = columns.iter().map(|&column| table_cell(e, column, &shared, opts)).collect();
+        table.add_row(row);
+    }
+
+    writeln!(out, "{table}")?;
+
+    if !shared.is_empty() {
+        writeln!(out, "* more than one process is listening on this port")?;
+    }
+    if has_project {
+        writeln!(out, "+ expected by the current project (.env, package.json or compose file)")?;
     }
+    writeln!(out, "{summary}")?;
+    if !opts.quiet
+        && columns.contains(&Column::Mem)
+        && let Some(totals) = resource_totals(&entries)
+    {
+        writeln!(out, "{totals}")?;
+    }
+    Ok(())
+}
 
+/// "Total: 3.2 GB RSS, 145% CPU" over the listed processes, counting each PID
+/// once; None unless resource usage was fetched
+fn resource_totals(entries: &[PortEntry]) -> Option<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut memory_kb = 0;
+    let mut cpu_percent = 0.0;
     for e in entries {
-        let category_cell = if colors {
-            Cell::new(format_kind(e.kind))
-                .fg(get_kind_color(e.kind))
-        } else {
-            Cell::new(format_kind(e.kind))
-        };
+        if let (Some(pid), Some(kb)) = (e.pid, e.memory_kb)
+            && seen.insert(pid)
+        {
+            memory_kb += kb;
+            cpu_percent += e.cpu_percent.unwrap_or(0.0);
+        }
+    }
+    if seen.is_empty() {
+        return None;
+    }
+    let memory = if memory_kb >= 1024 * 1024 {
+        format!("{} GB", format_float(memory_kb as f64 / (1024.0 * 1024.0), 1))
+    } else {
+        format!("{} MB", format_mb(memory_kb))
+    };
+    Some(format!("Total: {} RSS, {}% CPU", memory, format_float(cpu_percent, 0)))
+}
 
-        if verbose {
-            table.add_row(vec![
-                Cell::new(e.port),
-                Cell::new(e.process.unwrap_or("-".into())),
-                category_cell,
-                Cell::new(e.pid.map(|p| p.to_string()).unwrap_or("-".into())),
-                Cell::new(e.exec_path.unwrap_or("-".into())),
-            ]);
-        } else {
-            table.add_row(vec![
-                Cell::new(e.port),
-                Cell::new(e.process.unwrap_or("-".into())),
-                category_cell,
-                Cell::new(e.pid.map(|p| p.to_string()).unwrap_or("-".into())),
-            ]);
+/// The columns shown without --columns: the basics, the verbose extras, and
+/// whichever optional columns have data
+fn default_columns(entries: &[PortEntry], opts: &TableOptions) -> Vec<Column> {
+    let mut columns = vec![Column::Port, Column::Process, Column::Kind, Column::Pid];
+    if entries.iter().any(|e| e.service.is_some()) {
+        columns.push(Column::Service);
+    }
+    if opts.verbose {
+        columns.extend([Column::Bind, Column::Exec]);
+    }
+    if entries.iter().any(|e| e.cwd.is_some()) {
+        columns.push(Column::Cwd);
+    }
+    if entries.iter().any(|e| e.connections.is_some()) {
+        columns.push(Column::Conns);
+    }
+    if entries.iter().any(|e| e.health.is_some()) {
+        columns.push(Column::Health);
+    }
+    if entries.iter().any(|e| e.note.is_some()) {
+        columns.push(Column::Note);
+    }
+    columns
+}
+
+fn table_cell(
+    e: &PortEntry,
+    column: Column,
+    shared: &std::collections::BTreeMap<u16, Vec<u32>>,
+    opts: &TableOptions,
+) -> Cell {
+    match column {
+        Column::Port => {
+            let mut port_label = e.port.to_string();
+            for port in &e.other_ports {
+                port_label.push_str(&format!(", {port}"));
+            }
+            if shared.contains_key(&e.port) {
+                port_label.push_str(" *");
+            }
+            if e.project {
+                port_label.push_str(" +");
+            }
+            if e.project && opts.colors {
+                Cell::new(port_label).fg(Color::Green).add_attribute(Attribute::Bold)
+            } else {
+                Cell::new(port_label)
+            }
+        }
+        Column::Process => {
+            let process = e.process.as_deref().unwrap_or("-");
+            match e.brew_service {
+                Some(_) => Cell::new(format!("{} (brew)", process)),
+                None => Cell::new(process),
+            }
+        }
+        Column::Kind if opts.colors => Cell::new(format_kind(e.kind)).fg(get_kind_color(e.kind)),
+        Column::Kind => Cell::new(format_kind(e.kind)),
+        Column::Pid => Cell::new(e.pid.map(|p| p.to_string()).unwrap_or("-".into())),
+        Column::Service => Cell::new(e.service.as_deref().unwrap_or("-")),
+        Column::Bind => {
+            let scope = format_bind_scope(&e.addresses);
+            Cell::new(if is_privileged_port(e.port) {
+                format!("{} (privileged)", scope)
+            } else {
+                scope.to_string()
+            })
+        }
+        Column::Exec => Cell::new(e.exec_path.as_deref().unwrap_or("-")),
+        Column::Cwd => Cell::new(e.cwd.as_deref().map_or("-".into(), tilde_path)),
+        Column::Mem => Cell::new(e.memory_kb.map_or("-".into(), |kb| format!("{} MB", format_mb(kb)))),
+        Column::Conns => Cell::new(e.connections.map(|c| c.to_string()).unwrap_or("-".into())),
+        Column::Health => {
+            let health_cell = Cell::new(format_health(e.health));
+            match e.health {
+                Some(Health::Ok) if opts.colors => health_cell.fg(Color::Green),
+                Some(_) if opts.colors => health_cell.fg(Color::Red),
+                _ => health_cell,
+            }
         }
+        Column::Note => Cell::new(e.note.as_deref().unwrap_or("")),
     }
+}
 
-    println!("{table}");
+/// "18 ports across 7 processes": one process often listens on several ports,
+/// so the port count alone overstates how much is running
+fn summarize(entries: &[PortEntry]) -> String {
+    let plural = |n: usize, word: &str, suffix: &str| {
+        if n == 1 { format!("{n} {word}") } else { format!("{n} {word}{suffix}") }
+    };
+    let pids: std::collections::HashSet<u32> = entries.iter().filter_map(|e| e.pid).collect();
+    let count = entries.iter().map(|e| 1 + e.other_ports.len()).sum();
+    let ports = plural(count, "port", "s");
+    if pids.is_empty() {
+        ports
+    } else {
+        format!("{} across {}", ports, plural(pids.len(), "process", "es"))
+    }
 }
 
 fn format_kind(kind: Kind) -> &'static str {
@@ -1291,17 +6901,368 @@ fn format_kind(kind: Kind) -> &'static str {
         Kind::Dev => "Dev Server",
         Kind::Database => "Database",
         Kind::Container => "Container",
+        Kind::Forward => "Forwarded",
+        Kind::Worker => "Worker",
         Kind::System => "System",
         Kind::Unknown => "Unknown",
     }
 }
 
+/// Stable lowercase name of a kind, as used in JSON and on the command line
+fn format_kind_id(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Dev => "dev",
+        Kind::Database => "database",
+        Kind::Container => "container",
+        Kind::Forward => "forward",
+        Kind::Worker => "worker",
+        Kind::System => "system",
+        Kind::Unknown => "unknown",
+    }
+}
+
+/// The escape sequence comfy-table (via crossterm) emits for a foreground
+/// color, so hand-written output matches the tables exactly
+fn ansi_fg(color: Color) -> Cow<'static, str> {
+    let index = match color {
+        Color::Reset => return Cow::Borrowed("\x1b[39m"),
+        Color::Rgb { r, g, b } => return Cow::Owned(format!("\x1b[38;2;{r};{g};{b}m")),
+        Color::AnsiValue(value) => value,
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+    };
+    Cow::Owned(format!("\x1b[38;5;{index}m"))
+}
+
 fn get_kind_color(kind: Kind) -> Color {
     match kind {
         Kind::Dev => Color::Green,
         Kind::Database => Color::Cyan,
         Kind::Container => Color::Blue,
+        Kind::Forward => Color::Magenta,
+        Kind::Worker => Color::Grey,
         Kind::System => Color::Yellow,
         Kind::Unknown => Color::Red,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_line_keeps_process_last() {
+        let mut out = Vec::new();
+        print_compact_line(
+            6379,
+            None,
+            Kind::Container,
+            "redis (container)",
+            Some("/Applications/Redis Stack.app/redis"),
+            false,
+            &mut out,
+        )
+        .unwrap();
+        let line = String::from_utf8(out).unwrap();
+        assert_eq!(line, "6379 - container /Applications/Redis%20Stack.app/redis redis (container)\n");
+        let fields: Vec<&str> = line.trim_end().splitn(5, ' ').collect();
+        assert_eq!(fields[4], "redis (container)");
+    }
+
+    #[test]
+    fn redact_env_hides_credentials() {
+        let mut env = vec![
+            ("DATABASE_URL".to_string(), "postgres://app:hunter2@db/app".to_string()),
+            ("STRIPE_API_KEY".to_string(), "sk_live_123".to_string()),
+            ("github_token".to_string(), "ghp_abc".to_string()),
+            ("DB_PASSWORD".to_string(), "hunter2".to_string()),
+            ("SESSION_SECRET".to_string(), "s3cr3t".to_string()),
+            ("NODE_ENV".to_string(), "development".to_string()),
+            ("PORT".to_string(), "3000".to_string()),
+        ];
+        redact_env(&mut env);
+        let value = |key: &str| env.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        for key in ["DATABASE_URL", "STRIPE_API_KEY", "github_token", "DB_PASSWORD", "SESSION_SECRET"] {
+            assert_eq!(value(key), Some("<redacted>"), "{key}");
+        }
+        assert_eq!(value("NODE_ENV"), Some("development"));
+        assert_eq!(value("PORT"), Some("3000"));
+    }
+
+    fn mapping(host_start: u16, host_end: u16, protocol: Protocol) -> DockerPortMapping {
+        DockerPortMapping { host_start, host_end, protocol }
+    }
+
+    #[test]
+    fn docker_ports_with_ipv4_and_ipv6_host_bindings() {
+        assert_eq!(parse_docker_port_mappings("0.0.0.0:8080->80/tcp"), [mapping(8080, 8080, Protocol::Tcp)]);
+        assert_eq!(parse_docker_port_mappings("[::]:8080->80/tcp"), [mapping(8080, 8080, Protocol::Tcp)]);
+        assert_eq!(parse_docker_port_mappings(":::8080->80/tcp"), [mapping(8080, 8080, Protocol::Tcp)]);
+        assert_eq!(parse_docker_port_mappings("127.0.0.1:5432->5432/tcp"), [mapping(5432, 5432, Protocol::Tcp)]);
+        assert_eq!(parse_docker_port_mappings("8080->80/tcp"), [mapping(8080, 8080, Protocol::Tcp)]);
+    }
+
+    #[test]
+    fn docker_port_ranges() {
+        assert_eq!(
+            parse_docker_port_mappings("0.0.0.0:5000-5005->5000-5005/udp"),
+            [mapping(5000, 5005, Protocol::Udp)]
+        );
+        assert_eq!(
+            parse_docker_port_mappings("[::]:7000-7001->7000-7001/tcp"),
+            [mapping(7000, 7001, Protocol::Tcp)]
+        );
+    }
+
+    #[test]
+    fn docker_ports_skip_malformed_input() {
+        assert!(parse_docker_port_mappings("").is_empty());
+        // Exposed but not published
+        assert!(parse_docker_port_mappings("80/tcp").is_empty());
+        assert!(parse_docker_port_mappings("0.0.0.0:http->80/tcp").is_empty());
+        assert!(parse_docker_port_mappings("0.0.0.0:70000->80/tcp").is_empty());
+        // Reversed range
+        assert!(parse_docker_port_mappings("0.0.0.0:5005-5000->5000-5005/tcp").is_empty());
+        assert!(parse_docker_port_mappings("0.0.0.0:132->132/sctp").is_empty());
+    }
+
+    #[cfg(any(target_os = "macos", windows))]
+    fn container(name: &str, ports: &str) -> DockerContainer {
+        DockerContainer {
+            id: String::new(),
+            name: name.to_string(),
+            image: format!("{name}:latest"),
+            status: String::new(),
+            mounts: String::new(),
+            ports: parse_docker_port_mappings(ports),
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", windows))]
+    fn container_map_single_port() {
+        let map = container_port_map(&[container("db", "0.0.0.0:5432->5432/tcp")]);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&5432).map(|(name, _)| name.as_str()), Some("db"));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", windows))]
+    fn container_map_expands_ranges() {
+        let map = container_port_map(&[container("web", "0.0.0.0:8000-8005->8000-8005/tcp")]);
+        let mut ports: Vec<u16> = map.keys().copied().collect();
+        ports.sort();
+        assert_eq!(ports, (8000..=8005).collect::<Vec<_>>());
+        assert!(map.values().all(|(name, _)| name == "web"));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", windows))]
+    fn container_map_mixed_list() {
+        let map = container_port_map(&[
+            container("app", "0.0.0.0:3000->3000/tcp, [::]:3000->3000/tcp, 0.0.0.0:9000-9001->9000-9001/tcp, 0.0.0.0:53->53/udp"),
+            container("cache", "6379/tcp, 127.0.0.1:6379->6379/tcp"),
+        ]);
+        let mut ports: Vec<u16> = map.keys().copied().collect();
+        ports.sort();
+        // UDP and unpublished ports can't belong to a TCP listener
+        assert_eq!(ports, [3000, 6379, 9000, 9001]);
+        assert_eq!(map.get(&9001).map(|(name, _)| name.as_str()), Some("app"));
+        assert_eq!(map.get(&6379).map(|(name, _)| name.as_str()), Some("cache"));
+    }
+
+    fn classify_command(port: u16, process: &str, exec_path: &str, command: &str) -> Kind {
+        classify(port, Some(process), Some(exec_path), Some(command), "127.0.0.1:0", &Config::default())
+    }
+
+    #[test]
+    fn classify_django_under_python_as_dev() {
+        let cmd = "/Users/me/app/.venv/bin/python manage.py runserver 8765";
+        assert_eq!(classify_command(8765, "python3", "/Users/me/app/.venv/bin/python", cmd), Kind::Dev);
+        // Even on the system python, the Django dev server is a dev server
+        let cmd = "/usr/bin/python3 manage.py runserver 8765";
+        assert_eq!(classify_command(8765, "python3", "/usr/bin/python3", cmd), Kind::Dev);
+    }
+
+    #[test]
+    fn classify_system_python_by_port() {
+        let cmd = "/usr/bin/python3 /usr/libexec/some_daemon.py";
+        assert_eq!(classify_command(12345, "python3", "/usr/bin/python3", cmd), Kind::Unknown);
+    }
+
+    #[test]
+    fn classify_next_only_by_its_binary() {
+        let cmd = "node /Users/me/site/node_modules/.bin/next dev -p 4321";
+        assert_eq!(classify_command(4321, "node", "/opt/homebrew/bin/node", cmd), Kind::Dev);
+        assert_eq!(classify_command(4321, "next-server", "/opt/homebrew/bin/node", "next-server"), Kind::Dev);
+        // Tools that merely contain "next" in their name or arguments
+        let cmd = "/usr/local/bin/nextdns run --listen 127.0.0.1:5353";
+        assert_eq!(classify_command(5353, "nextdns", "/usr/local/bin/nextdns", cmd), Kind::Unknown);
+        let cmd = "/usr/sbin/connectd --next-hop 10.0.0.1";
+        assert_eq!(classify_command(7001, "connectd", "/usr/sbin/connectd", cmd), Kind::Unknown);
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn normalize_address_without_state_suffix() {
+        assert_eq!(normalize_address(IpFamily::V4, "127.0.0.1:5432"), "127.0.0.1:5432");
+        assert_eq!(normalize_address(IpFamily::V4, "*:3000"), "0.0.0.0:3000");
+        assert_eq!(normalize_address(IpFamily::V6, "*:3000"), "[::]:3000");
+        assert_eq!(normalize_address(IpFamily::V6, "[::1]:8080"), "[::1]:8080");
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn normalize_address_strips_state_suffix() {
+        assert_eq!(normalize_address(IpFamily::V4, "127.0.0.1:5432 (LISTEN)"), "127.0.0.1:5432");
+        assert_eq!(normalize_address(IpFamily::V4, "*:3000 (LISTEN)"), "0.0.0.0:3000");
+        assert_eq!(normalize_address(IpFamily::V6, "*:3000 (LISTEN)"), "[::]:3000");
+        assert_eq!(normalize_address(IpFamily::V6, "[fe80::1%lo0]:631 (LISTEN)"), "[fe80::1%lo0]:631");
+    }
+
+    /// A KERN_PROCARGS2 buffer: argc, the exec path with padding, argv, then env
+    #[cfg(target_os = "macos")]
+    fn procargs2(path: &str, argv: &[&str], env: &[&str]) -> Vec<u8> {
+        let mut buf = (argv.len() as i32).to_ne_bytes().to_vec();
+        buf.extend_from_slice(path.as_bytes());
+        buf.extend_from_slice(&[0, 0, 0]);
+        for s in argv.iter().chain(env) {
+            buf.extend_from_slice(s.as_bytes());
+            buf.push(0);
+        }
+        buf.push(0);
+        buf
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn procargs2_keeps_spaces_and_equals_in_values() {
+        let buf = procargs2(
+            "/usr/local/bin/node",
+            &["node", "server.js", "--title=my app"],
+            &["PATH=/a b/c:/usr/bin", "FOO=k=v", "EMPTY="],
+        );
+        let env = parse_procargs2(&buf).unwrap();
+        assert_eq!(
+            env,
+            [
+                ("PATH".to_string(), "/a b/c:/usr/bin".to_string()),
+                ("FOO".to_string(), "k=v".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn procargs2_truncated_buffers() {
+        // Not even argc
+        assert_eq!(parse_procargs2(&[1, 0]), None);
+        // Cut off inside the exec path
+        let mut buf = 1i32.to_ne_bytes().to_vec();
+        buf.extend_from_slice(b"/usr/bin/pyth");
+        assert_eq!(parse_procargs2(&buf), None);
+        // Cut off inside argv: no environment to report
+        let buf = procargs2("/usr/bin/python3", &["python3", "manage.py", "runserver"], &["HOME=/Users/me"]);
+        assert_eq!(parse_procargs2(&buf[..buf.len() - 30]), Some(Vec::new()));
+    }
+
+    #[test]
+    fn snapshot_schema_is_checked() {
+        let saved = serde_json::to_string(&Snapshot { schema: SNAPSHOT_SCHEMA, ports: Vec::new() }).unwrap();
+        assert!(parse_snapshot("saved.json", &saved).unwrap().is_empty());
+        assert!(parse_snapshot("legacy.json", "[]").unwrap().is_empty());
+
+        let err = parse_snapshot("new.json", r#"{"schema": 2, "ports": []}"#).unwrap_err();
+        assert_eq!(err.to_string(), "new.json was saved with snapshot schema 2, but this porty reads schema 1");
+
+        let err = parse_snapshot("failed.json", r#"{"error": "lsof not found", "schema": 1}"#).unwrap_err();
+        assert_eq!(err.to_string(), "failed.json records a failed scan: lsof not found");
+    }
+
+    #[test]
+    fn tui_filter_takes_q_but_ctrl_c_quits() {
+        let mut state = TuiState {
+            entries: Vec::new(),
+            selected: 0,
+            filter: String::new(),
+            editing_filter: true,
+            detail: None,
+            confirm_kill: None,
+            status: String::new(),
+            docker_enabled: false,
+        };
+        assert!(state.handle(TuiKey::Char('q')));
+        assert_eq!(state.filter, "q");
+        assert!(!state.handle(TuiKey::Quit));
+    }
+
+    #[test]
+    fn only_ssh_clients_with_local_forwards_are_forwards() {
+        let forward = |process: &str, command: &str| classify_command(8080, process, "/usr/bin/ssh", command);
+        assert_eq!(forward("ssh", "ssh -L 8080:db:5432 bastion"), Kind::Forward);
+        assert_eq!(forward("ssh", "ssh -fNL 8080:db:5432 bastion"), Kind::Forward);
+        assert_eq!(forward("ssh", "ssh -D1080 bastion"), Kind::Forward);
+        assert_ne!(forward("ssh", "ssh -oLogLevel=ERROR bastion"), Kind::Forward);
+        assert_ne!(forward("ssh", "ssh -R 8080:localhost:3000 bastion"), Kind::Forward);
+        assert_ne!(forward("sshd", "sshd: me@pts/0"), Kind::Forward);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn netstat_listeners_in_any_language() {
+        assert_eq!(
+            netstat_listener("  TCP    0.0.0.0:3000     0.0.0.0:0        LISTENING       1234"),
+            Some((3000, 1234, "0.0.0.0:3000"))
+        );
+        assert_eq!(
+            netstat_listener("  TCP    [::]:5432        [::]:0           ABHÖREN         88"),
+            Some((5432, 88, "[::]:5432"))
+        );
+        assert_eq!(netstat_listener("  TCP    127.0.0.1:3000   127.0.0.1:54321  ESTABLISHED     1234"), None);
+        assert_eq!(netstat_listener("  UDP    0.0.0.0:5353     *:*                              2048"), None);
+    }
+
+    #[test]
+    fn several_ports_declare_each_prometheus_family_once() {
+        let entry: PortEntry = serde_json::from_str(
+            r#"{"port": 8080, "pid": 42, "process": "node", "kind": "dev", "addresses": [], "protocol": "tcp"}"#,
+        )
+        .unwrap();
+        let outputs = [Some(PortOutput::Listing(vec![entry])), None, None];
+        let mut out = Vec::new();
+        print_ports_prometheus(&[8080, 9000, 9001], &outputs, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches("# HELP porty_port_listening").count(), 1);
+        assert_eq!(text.matches("# TYPE porty_port_listening").count(), 1);
+        assert!(text.contains("porty_port_listening{port=\"8080\",pid=\"42\",process=\"node\",kind=\"dev\"} 1"));
+        assert!(text.contains("porty_port_listening{port=\"9000\"} 0"));
+        assert!(text.contains("porty_port_listening{port=\"9001\"} 0"));
+    }
+
+    #[test]
+    fn jitter_alone_is_not_backing_off() {
+        let schedule = WatchSchedule { interval: Duration::from_secs(2), jitter: Duration::from_secs(3) };
+        let (wait, backing_off) = schedule.next_wait(Duration::from_millis(100));
+        assert!(wait >= Duration::from_secs(2) && wait <= Duration::from_secs(5));
+        assert!(!backing_off);
+
+        let (wait, backing_off) = schedule.next_wait(Duration::from_secs(3));
+        assert!(wait >= Duration::from_secs(6));
+        assert!(backing_off);
+    }
+}